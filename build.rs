@@ -1,8 +1,7 @@
 fn main() {
-    cc::Build::new()
-        .file("src/imgui_vita_touch_wrapper.cpp")
-        .file("imgui-vita/imgui_vita_touch.cpp")
-        .compile("imgui_vita_touch");
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("vita") {
+        return;
+    }
 
     if let Ok(sdk) = std::env::var("VITASDK").map(std::path::PathBuf::from) {
         let lib_dir = sdk.join("arm-vita-eabi").join("lib");
@@ -1,7 +1,16 @@
-use imgui_rs_vitagl_renderer::ImguiRenderer;
+use imgui_rs_vitagl_renderer::{ImguiRenderer, VitaPlatform};
 
 fn main() {
-    let mut renderer = ImguiRenderer::new();
-    renderer.new_frame();
-    renderer.render();
+    let mut imgui = imgui::Context::create();
+    imgui.set_ini_filename(None);
+
+    let mut platform = VitaPlatform::init(&mut imgui);
+    let mut renderer = ImguiRenderer::try_new(&mut imgui).unwrap();
+
+    platform.prepare_frame(imgui.io_mut());
+    renderer.prepare_frame(imgui.fonts()).unwrap();
+    let ui = imgui.new_frame();
+    ui.show_demo_window(&mut true);
+
+    renderer.render(imgui.render()).unwrap();
 }
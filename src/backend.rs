@@ -0,0 +1,347 @@
+//! Traits hiding everything the renderer needs from the console, so it can be driven by a mock
+//! on a host machine. The vitaGL/vitasdk implementations live in the `vita` module.
+
+use std::ffi::c_void;
+
+use crate::gl::*;
+
+pub const SCE_CTRL_SELECT: u32 = 0x00000001;
+pub const SCE_CTRL_L3: u32 = 0x00000002;
+pub const SCE_CTRL_R3: u32 = 0x00000004;
+pub const SCE_CTRL_START: u32 = 0x00000008;
+pub const SCE_CTRL_UP: u32 = 0x00000010;
+pub const SCE_CTRL_RIGHT: u32 = 0x00000020;
+pub const SCE_CTRL_DOWN: u32 = 0x00000040;
+pub const SCE_CTRL_LEFT: u32 = 0x00000080;
+pub const SCE_CTRL_LTRIGGER: u32 = 0x00000100;
+pub const SCE_CTRL_RTRIGGER: u32 = 0x00000200;
+pub const SCE_CTRL_L1: u32 = 0x00000400;
+pub const SCE_CTRL_R1: u32 = 0x00000800;
+pub const SCE_CTRL_TRIANGLE: u32 = 0x00001000;
+pub const SCE_CTRL_CIRCLE: u32 = 0x00002000;
+pub const SCE_CTRL_CROSS: u32 = 0x00004000;
+pub const SCE_CTRL_SQUARE: u32 = 0x00008000;
+
+/// A controller sample, mirroring the fields of `SceCtrlData` the renderer reads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PadState {
+    pub buttons: u32,
+    pub lx: u8,
+    pub ly: u8,
+    pub rx: u8,
+    pub ry: u8,
+}
+
+/// Most fingers a touch panel reports, `SCE_TOUCH_MAX_REPORT`.
+pub const MAX_TOUCH_POINTS: usize = 8;
+
+/// Active areas of the touch panels in report coordinates: min x, min y, max x, max y.
+const FRONT_PANEL_AREA: [f32; 4] = [0., 0., 1920., 1088.];
+const BACK_PANEL_AREA: [f32; 4] = [0., 108., 1920., 890.];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TouchPanel {
+    Front,
+    Back,
+}
+
+impl TouchPanel {
+    fn area(self) -> [f32; 4] {
+        match self {
+            Self::Front => FRONT_PANEL_AREA,
+            Self::Back => BACK_PANEL_AREA,
+        }
+    }
+}
+
+/// A finger on a touch panel. `x` and `y` run from 0 to 1 across the panel's active area, as
+/// seen from the front of the console.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TouchPoint {
+    pub id: u8,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl TouchPoint {
+    /// Scales a `SceTouchReport` position from `panel`'s report coordinates, clamping reports
+    /// outside its active area to the edge.
+    pub fn from_report(panel: TouchPanel, id: u8, x: u16, y: u16) -> Self {
+        let area = panel.area();
+        Self {
+            id,
+            x: ((x as f32 - area[0]) / (area[2] - area[0])).clamp(0., 1.),
+            y: ((y as f32 - area[1]) / (area[3] - area[1])).clamp(0., 1.),
+        }
+    }
+}
+
+/// A touch panel sample.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TouchState {
+    pub count: usize,
+    pub points: [TouchPoint; MAX_TOUCH_POINTS],
+}
+
+impl TouchState {
+    pub fn points(&self) -> &[TouchPoint] {
+        &self.points[..self.count.min(MAX_TOUCH_POINTS)]
+    }
+}
+
+/// Touch panels sampled by the input backend.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TouchPorts {
+    None,
+    #[default]
+    Front,
+    Back,
+    Both,
+}
+
+impl TouchPorts {
+    pub fn front(self) -> bool {
+        matches!(self, Self::Front | Self::Both)
+    }
+
+    pub fn back(self) -> bool {
+        matches!(self, Self::Back | Self::Both)
+    }
+}
+
+/// Controller sampling mode, mirroring `SceCtrlPadInputMode`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CtrlMode {
+    Digital,
+    Analog,
+    #[default]
+    AnalogWide,
+}
+
+/// The GL entry points used by the renderer. Method names follow the GL/vitaGL function they
+/// stand for.
+pub trait GlBackend {
+    fn get_error(&mut self) -> GLenum;
+    fn get_integerv(&mut self, pname: GLenum, data: &mut [GLint]);
+    fn gen_texture(&mut self) -> GLuint;
+    fn delete_texture(&mut self, texture: GLuint);
+    fn bind_texture(&mut self, target: GLenum, texture: GLuint);
+    fn tex_parameteri(&mut self, target: GLenum, pname: GLenum, param: GLint);
+    #[allow(clippy::too_many_arguments)]
+    fn tex_image_2d(
+        &mut self,
+        target: GLenum,
+        level: GLint,
+        internal_format: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+        ty: GLenum,
+        pixels: &[u8],
+    );
+    fn generate_mipmap(&mut self, target: GLenum);
+
+    fn use_program(&mut self, program: GLuint);
+    fn enable(&mut self, cap: GLenum);
+    fn disable(&mut self, cap: GLenum);
+    fn is_enabled(&mut self, cap: GLenum) -> bool;
+    fn blend_func(&mut self, sfactor: GLenum, dfactor: GLenum);
+    fn enable_client_state(&mut self, array: GLenum);
+    fn disable_client_state(&mut self, array: GLenum);
+    fn polygon_mode(&mut self, face: GLenum, mode: GLenum);
+    fn viewport(&mut self, x: GLint, y: GLint, width: GLsizei, height: GLsizei);
+    fn scissor(&mut self, x: GLint, y: GLint, width: GLsizei, height: GLsizei);
+
+    fn matrix_mode(&mut self, mode: GLenum);
+    fn push_matrix(&mut self);
+    fn pop_matrix(&mut self);
+    fn load_identity(&mut self);
+    fn ortho(&mut self, left: f64, right: f64, bottom: f64, top: f64, near: f64, far: f64);
+
+    /// # Safety
+    ///
+    /// `pointer` must point to `u16` indices that stay valid until the last draw using them.
+    unsafe fn index_pointer_mapped(&mut self, pointer: *const c_void);
+    /// # Safety
+    ///
+    /// `pointer` must point to `size` floats per vertex that stay valid until the next draw.
+    unsafe fn vertex_pointer_mapped(&mut self, size: GLint, pointer: *const c_void);
+    /// # Safety
+    ///
+    /// `pointer` must point to two floats per vertex that stay valid until the next draw.
+    unsafe fn tex_coord_pointer_mapped(&mut self, pointer: *const c_void);
+    /// # Safety
+    ///
+    /// `pointer` must point to four `ty` components per vertex that stay valid until the next
+    /// draw.
+    unsafe fn color_pointer_mapped(&mut self, ty: GLenum, pointer: *const c_void);
+    /// # Safety
+    ///
+    /// `pointer` must stay valid until the next draw.
+    unsafe fn vertex_attrib_pointer_mapped(&mut self, index: GLuint, pointer: *const c_void);
+    /// # Safety
+    ///
+    /// Every pointer set through the `*_pointer_mapped` calls must still be valid for `count`
+    /// vertices, and for `count` indices when an index pointer is set.
+    unsafe fn draw_objects(&mut self, mode: GLenum, count: GLsizei, implicit_wvp: bool);
+
+    /// # Safety
+    ///
+    /// `pointer` must address `size` components of `ty` every `stride` bytes, valid until the
+    /// next draw.
+    unsafe fn vertex_pointer(
+        &mut self,
+        size: GLint,
+        ty: GLenum,
+        stride: GLsizei,
+        pointer: *const c_void,
+    );
+    /// # Safety
+    ///
+    /// Same as `vertex_pointer`.
+    unsafe fn tex_coord_pointer(
+        &mut self,
+        size: GLint,
+        ty: GLenum,
+        stride: GLsizei,
+        pointer: *const c_void,
+    );
+    /// # Safety
+    ///
+    /// Same as `vertex_pointer`.
+    unsafe fn color_pointer(
+        &mut self,
+        size: GLint,
+        ty: GLenum,
+        stride: GLsizei,
+        pointer: *const c_void,
+    );
+    /// # Safety
+    ///
+    /// `indices` must point to `count` indices of `ty`, all within the bound vertex arrays.
+    unsafe fn draw_elements(
+        &mut self,
+        mode: GLenum,
+        count: GLsizei,
+        ty: GLenum,
+        indices: *const c_void,
+    );
+
+    fn create_shader(&mut self, ty: GLenum) -> GLuint;
+    fn shader_source(&mut self, shader: GLuint, source: &str);
+    fn compile_shader(&mut self, shader: GLuint);
+    fn get_shaderiv(&mut self, shader: GLuint, pname: GLenum) -> GLint;
+    fn get_shader_info_log(&mut self, shader: GLuint) -> String;
+    fn delete_shader(&mut self, shader: GLuint);
+    fn create_program(&mut self) -> GLuint;
+    fn attach_shader(&mut self, program: GLuint, shader: GLuint);
+    /// vitaGL's `vglBindAttribLocation`, which also declares the format the attribute is read
+    /// with by `vertex_attrib_pointer_mapped`.
+    fn bind_attrib_location(
+        &mut self,
+        program: GLuint,
+        index: GLuint,
+        name: &str,
+        size: GLint,
+        ty: GLenum,
+    );
+    fn link_program(&mut self, program: GLuint);
+    fn get_programiv(&mut self, program: GLuint, pname: GLenum) -> GLint;
+    fn get_program_info_log(&mut self, program: GLuint) -> String;
+    fn delete_program(&mut self, program: GLuint);
+    fn get_uniform_location(&mut self, program: GLuint, name: &str) -> GLint;
+    fn uniform_matrix4fv(&mut self, location: GLint, value: &[f32; 16]);
+    fn uniform1i(&mut self, location: GLint, value: GLint);
+    fn enable_vertex_attrib_array(&mut self, index: GLuint);
+    fn disable_vertex_attrib_array(&mut self, index: GLuint);
+    /// # Safety
+    ///
+    /// Same as `vertex_pointer`.
+    unsafe fn vertex_attrib_pointer(
+        &mut self,
+        index: GLuint,
+        size: GLint,
+        ty: GLenum,
+        normalized: bool,
+        stride: GLsizei,
+        pointer: *const c_void,
+    );
+}
+
+/// Controller and touch sampling.
+pub trait InputBackend {
+    /// Starts sampling the given touch ports and the controller. Called once by
+    /// `VitaPlatformBuilder::build_with_backends`.
+    fn init(&mut self, touch_ports: TouchPorts, ctrl_mode: CtrlMode);
+    fn read_pad(&mut self) -> PadState;
+    /// Samples a touch panel. Backends without touch input report no fingers.
+    fn read_touch(&mut self, _panel: TouchPanel) -> TouchState {
+        TouchState::default()
+    }
+}
+
+/// A monotonic clock with microsecond resolution.
+pub trait Clock {
+    fn now_micros(&mut self) -> u64;
+}
+
+/// A `Clock` that moves forward by a fixed step on every read, for deterministic tests and
+/// replays.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FixedStepClock {
+    now: u64,
+    step: u64,
+}
+
+impl FixedStepClock {
+    pub fn new(step_micros: u64) -> Self {
+        Self {
+            now: 0,
+            step: step_micros,
+        }
+    }
+
+    /// Moves the clock forward once, e.g. to simulate a suspend.
+    pub fn advance(&mut self, micros: u64) {
+        self.now += micros;
+    }
+}
+
+impl Clock for FixedStepClock {
+    fn now_micros(&mut self) -> u64 {
+        self.now += self.step;
+        self.now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(panel: TouchPanel, x: u16, y: u16) -> [f32; 2] {
+        let point = TouchPoint::from_report(panel, 0, x, y);
+        [point.x, point.y]
+    }
+
+    #[test]
+    fn front_reports_cover_the_panel() {
+        assert_eq!(position(TouchPanel::Front, 0, 0), [0., 0.]);
+        assert_eq!(position(TouchPanel::Front, 960, 544), [0.5, 0.5]);
+        assert_eq!(position(TouchPanel::Front, 1920, 1088), [1., 1.]);
+        assert_eq!(TouchPoint::from_report(TouchPanel::Front, 3, 0, 0).id, 3);
+    }
+
+    #[test]
+    fn back_reports_start_below_the_top_edge() {
+        assert_eq!(position(TouchPanel::Back, 0, 108), [0., 0.]);
+        assert_eq!(position(TouchPanel::Back, 480, 499), [0.25, 0.5]);
+        assert_eq!(position(TouchPanel::Back, 1920, 890), [1., 1.]);
+    }
+
+    #[test]
+    fn reports_outside_the_area_are_clamped() {
+        assert_eq!(position(TouchPanel::Front, 2000, 1200), [1., 1.]);
+        assert_eq!(position(TouchPanel::Back, 960, 20), [0.5, 0.]);
+        assert_eq!(position(TouchPanel::Back, 960, 1000), [0.5, 1.]);
+    }
+}
@@ -0,0 +1,42 @@
+use std::ffi::c_void;
+use std::ptr::NonNull;
+
+use crate::error::RendererError;
+
+/// `len` values of `T` in memory from `igMemAlloc`, freed through the base pointer on drop.
+/// Contents start uninitialized.
+pub(crate) struct MemBuffer<T> {
+    ptr: NonNull<T>,
+    len: usize,
+}
+
+impl<T: Copy> MemBuffer<T> {
+    pub(crate) fn alloc(len: usize) -> Result<Self, RendererError> {
+        let size = std::mem::size_of::<T>()
+            .checked_mul(len)
+            .ok_or(RendererError::Alloc(usize::MAX))?;
+        let ptr = unsafe { imgui::sys::igMemAlloc(size) } as *mut T;
+        match NonNull::new(ptr) {
+            Some(ptr) => Ok(Self { ptr, len }),
+            None => Err(RendererError::Alloc(size)),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const T {
+        self.ptr.as_ptr()
+    }
+
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+}
+
+impl<T> Drop for MemBuffer<T> {
+    fn drop(&mut self) {
+        unsafe { imgui::sys::igMemFree(self.ptr.as_ptr() as *mut c_void) }
+    }
+}
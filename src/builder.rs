@@ -0,0 +1,198 @@
+use std::fmt;
+
+use crate::backend::GlBackend;
+use crate::error::RendererError;
+use crate::registration::Registration;
+#[cfg(target_os = "vita")]
+use crate::vita::VitaGl;
+use crate::{
+    DrawMode, FontAtlasFormat, FrameAdvance, ImguiRenderer, OverflowPolicy, Pipeline, TextureFilter,
+};
+
+/// Largest identity index buffer a `u16` index can address.
+const MAX_INDEX_BUFFER_LEN: usize = u16::MAX as usize + 1;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    /// Each frame's share of the vertex pools must hold a full index buffer of vertices.
+    MempoolTooSmall(usize),
+    /// At least one frame must be in flight.
+    FramesInFlight(usize),
+    /// The identity index buffer must hold between 3 and 65536 indices.
+    IndexBufferLen(usize),
+    /// Touch input is enabled for a panel whose touch port is not sampled.
+    NoTouchPorts,
+    /// The display size must be positive and finite.
+    DisplaySize([f32; 2]),
+    /// The viewport must be finite with a positive size.
+    Viewport([f32; 4]),
+    /// The framebuffer scale must be positive and finite.
+    FramebufferScale([f32; 2]),
+    /// The stick mouse speed must be finite and not negative.
+    MousestickSpeed(f32),
+    /// The stick mouse acceleration must be finite and at least 1.
+    MousestickAcceleration(f32),
+    /// The max delta time must be finite and at least a microsecond, and the suspend threshold
+    /// no shorter than it.
+    DeltaTime(f32),
+    /// The context already has a backend of this kind, registered under this name.
+    AlreadyInitialized(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MempoolTooSmall(size) => write!(
+                f,
+                "mempool size {:#x} leaves less than an index buffer of vertices per frame",
+                size
+            ),
+            Self::FramesInFlight(frames) => write!(f, "invalid frames in flight {}", frames),
+            Self::IndexBufferLen(len) => write!(
+                f,
+                "index buffer length {:#x} must be between 3 and {:#x}",
+                len, MAX_INDEX_BUFFER_LEN
+            ),
+            Self::NoTouchPorts => write!(f, "touch input is enabled without sampling its panel"),
+            Self::DisplaySize(size) => write!(f, "invalid display size {:?}", size),
+            Self::Viewport(viewport) => write!(f, "invalid viewport {:?}", viewport),
+            Self::FramebufferScale(scale) => write!(f, "invalid framebuffer scale {:?}", scale),
+            Self::MousestickSpeed(speed) => write!(f, "invalid stick mouse speed {}", speed),
+            Self::MousestickAcceleration(acceleration) => {
+                write!(f, "invalid stick mouse acceleration {}", acceleration)
+            }
+            Self::DeltaTime(delta) => write!(f, "invalid delta time limit {}", delta),
+            Self::AlreadyInitialized(name) => {
+                write!(f, "context already has the backend {:?}", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Construction options for `ImguiRenderer`. The defaults match `ImguiRenderer::new`.
+#[derive(Clone, Debug)]
+pub struct ImguiRendererBuilder {
+    pub(crate) mempool_size: usize,
+    pub(crate) index_buffer_len: usize,
+    pub(crate) frames_in_flight: usize,
+    pub(crate) frame_advance: FrameAdvance,
+    pub(crate) overflow_policy: OverflowPolicy,
+    pub(crate) font_atlas_format: FontAtlasFormat,
+    pub(crate) texture_filter: TextureFilter,
+    pub(crate) mipmaps: bool,
+    pub(crate) draw_mode: DrawMode,
+    pub(crate) pipeline: Pipeline,
+}
+
+impl Default for ImguiRendererBuilder {
+    fn default() -> Self {
+        Self {
+            mempool_size: 0x200000,
+            index_buffer_len: 0xF000,
+            frames_in_flight: 3,
+            frame_advance: FrameAdvance::default(),
+            overflow_policy: OverflowPolicy::default(),
+            font_atlas_format: FontAtlasFormat::default(),
+            texture_filter: TextureFilter::default(),
+            mipmaps: false,
+            draw_mode: DrawMode::default(),
+            pipeline: Pipeline::default(),
+        }
+    }
+}
+
+impl ImguiRendererBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of vertices in each of the mapped position, texcoord and color pools, split evenly
+    /// between the frames in flight.
+    pub fn mempool_size(mut self, mempool_size: usize) -> Self {
+        self.mempool_size = mempool_size;
+        self
+    }
+
+    /// Number of indices in the identity index buffer. De-indexed draws with more elements are
+    /// split.
+    pub fn index_buffer_len(mut self, index_buffer_len: usize) -> Self {
+        self.index_buffer_len = index_buffer_len;
+        self
+    }
+
+    pub fn font_atlas_format(mut self, font_atlas_format: FontAtlasFormat) -> Self {
+        self.font_atlas_format = font_atlas_format;
+        self
+    }
+
+    /// Filter of the font atlas and user textures.
+    pub fn texture_filter(mut self, texture_filter: TextureFilter) -> Self {
+        self.texture_filter = texture_filter;
+        self
+    }
+
+    /// Generates mipmaps for the font atlas and user textures, for UIs drawn scaled down.
+    pub fn mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    /// Regions of the vertex pools kept untouched while the GPU may still read them. There are
+    /// no fences, so this counts region advances within the GPU's latency: with
+    /// `FrameAdvance::Render` that is `render` calls, and an app rendering twice per swap needs
+    /// twice as many. `FrameAdvance::Manual` makes it count swaps instead.
+    pub fn frames_in_flight(mut self, frames_in_flight: usize) -> Self {
+        self.frames_in_flight = frames_in_flight;
+        self
+    }
+
+    pub fn frame_advance(mut self, frame_advance: FrameAdvance) -> Self {
+        self.frame_advance = frame_advance;
+        self
+    }
+
+    pub fn overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.overflow_policy = overflow_policy;
+        self
+    }
+
+    pub fn draw_mode(mut self, draw_mode: DrawMode) -> Self {
+        self.draw_mode = draw_mode;
+        self
+    }
+
+    pub fn pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.index_buffer_len < 3 || self.index_buffer_len > MAX_INDEX_BUFFER_LEN {
+            return Err(ConfigError::IndexBufferLen(self.index_buffer_len));
+        }
+        if self.frames_in_flight == 0 {
+            return Err(ConfigError::FramesInFlight(self.frames_in_flight));
+        }
+        if self.mempool_size / self.frames_in_flight < self.index_buffer_len {
+            return Err(ConfigError::MempoolTooSmall(self.mempool_size));
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "vita")]
+    pub fn build(self, ctx: &mut imgui::Context) -> Result<ImguiRenderer, RendererError> {
+        self.build_with_backend(ctx, VitaGl)
+    }
+
+    pub fn build_with_backend<G: GlBackend>(
+        self,
+        ctx: &mut imgui::Context,
+        gl: G,
+    ) -> Result<ImguiRenderer<G>, RendererError> {
+        self.validate()?;
+        Registration::check(ctx.renderer_name())?;
+        ImguiRenderer::from_builder(self, ctx, gl)
+    }
+}
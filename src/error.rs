@@ -0,0 +1,68 @@
+use std::fmt;
+
+use imgui::TextureId;
+
+use crate::builder::ConfigError;
+use crate::gl::GLenum;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RendererError {
+    Config(ConfigError),
+    /// `igMemAlloc` returned null for a request of this many bytes, or `usize::MAX` if the size
+    /// overflowed.
+    Alloc(usize),
+    /// `glGetError` reported this code after a texture upload.
+    Gl(GLenum),
+    /// A shader failed to compile or the program failed to link; holds the info log.
+    Shader(String),
+    /// The pixel data does not match the texture size: `expected` bytes, or `usize::MAX` if the
+    /// size overflowed, `actual` given.
+    TextureData {
+        expected: usize,
+        actual: usize,
+    },
+    /// A draw command or texture call referred to a texture that is not registered.
+    UnknownTexture(TextureId),
+    /// A draw needed more room than a mapped buffer has.
+    BufferOverflow {
+        needed: usize,
+        capacity: usize,
+    },
+}
+
+impl fmt::Display for RendererError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Config(error) => write!(f, "invalid renderer configuration: {}", error),
+            Self::Alloc(size) => write!(f, "failed to allocate {} bytes", size),
+            Self::Gl(code) => write!(f, "gl error {:#x}", code),
+            Self::Shader(log) => write!(f, "failed to build the imgui shader program: {}", log),
+            Self::TextureData { expected, actual } => write!(
+                f,
+                "texture data has {} bytes, expected {}",
+                actual, expected
+            ),
+            Self::UnknownTexture(id) => write!(f, "unknown texture {}", id.id()),
+            Self::BufferOverflow { needed, capacity } => write!(
+                f,
+                "draw needs {} elements but the buffer holds {}",
+                needed, capacity
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RendererError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Config(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ConfigError> for RendererError {
+    fn from(error: ConfigError) -> Self {
+        Self::Config(error)
+    }
+}
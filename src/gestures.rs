@@ -0,0 +1,266 @@
+use crate::backend::TouchPoint;
+
+/// What the front panel reported this frame, as mouse input. Positions are normalized to the
+/// panel like `TouchPoint`s.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Gestures {
+    pub pointer: Option<[f32; 2]>,
+    pub left_down: bool,
+    pub right_down: bool,
+    pub wheel: [f32; 2],
+    /// Finger spread relative to when the second finger landed, while two fingers are down.
+    pub pinch: Option<f32>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Phase {
+    #[default]
+    Idle,
+    /// One finger down that has neither moved nor been held long enough to decide.
+    Pending {
+        start: [f32; 2],
+        held: f32,
+    },
+    Dragging,
+    LongPress,
+    /// A tap was reported as a press last frame and is released now.
+    TapRelease,
+    TwoFingers {
+        centroid: [f32; 2],
+        start_spread: f32,
+    },
+    /// Fingers left over from a two-finger gesture, ignored until all lift.
+    Ignored,
+}
+
+/// Turns front panel fingers into mouse input: one finger taps and drags with the left button,
+/// a long press holds the right button, and two fingers scroll and pinch.
+///
+/// A finger only presses the left button once it moves or lifts, so a long press never clicks.
+#[derive(Clone, Debug)]
+pub struct GestureRecognizer {
+    long_press_time: f32,
+    slop: f32,
+    scroll_speed: f32,
+    phase: Phase,
+    last_pointer: Option<[f32; 2]>,
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self {
+            long_press_time: 0.5,
+            slop: 0.02,
+            scroll_speed: 10.,
+            phase: Phase::Idle,
+            last_pointer: None,
+        }
+    }
+}
+
+impl GestureRecognizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seconds a finger rests before the right button goes down.
+    pub fn long_press_time(mut self, long_press_time: f32) -> Self {
+        self.long_press_time = long_press_time;
+        self
+    }
+
+    /// Distance, as a fraction of the panel, a finger may move and still count as resting.
+    pub fn slop(mut self, slop: f32) -> Self {
+        self.slop = slop;
+        self
+    }
+
+    /// Wheel steps of a two-finger drag across the whole panel. Negative values invert
+    /// scrolling.
+    pub fn scroll_speed(mut self, scroll_speed: f32) -> Self {
+        self.scroll_speed = scroll_speed;
+        self
+    }
+
+    /// Feeds one frame of fingers, `delta_time` seconds after the last.
+    pub fn update(&mut self, points: &[TouchPoint], delta_time: f32) -> Gestures {
+        let mut gestures = Gestures::default();
+        match points {
+            [] => {
+                if let Phase::Pending { .. } = self.phase {
+                    // Report the tap now; it is released next frame.
+                    gestures.pointer = self.last_pointer;
+                    gestures.left_down = true;
+                    self.phase = Phase::TapRelease;
+                } else {
+                    self.phase = Phase::Idle;
+                }
+            }
+            [point] => {
+                let position = [point.x, point.y];
+                gestures.pointer = Some(position);
+                match self.phase {
+                    Phase::Idle | Phase::TapRelease => {
+                        self.phase = Phase::Pending {
+                            start: position,
+                            held: 0.,
+                        }
+                    }
+                    Phase::Pending { start, held } => {
+                        let held = held + delta_time;
+                        let moved = (position[0] - start[0]).hypot(position[1] - start[1]);
+                        self.phase = if moved > self.slop {
+                            // Press where the finger landed so the drag starts there.
+                            gestures.pointer = Some(start);
+                            Phase::Dragging
+                        } else if held >= self.long_press_time {
+                            Phase::LongPress
+                        } else {
+                            Phase::Pending { start, held }
+                        };
+                    }
+                    Phase::TwoFingers { .. } => self.phase = Phase::Ignored,
+                    Phase::Dragging | Phase::LongPress | Phase::Ignored => {}
+                }
+                gestures.left_down = self.phase == Phase::Dragging;
+                gestures.right_down = self.phase == Phase::LongPress;
+            }
+            [first, second, ..] => {
+                let centroid = [(first.x + second.x) / 2., (first.y + second.y) / 2.];
+                let spread = (first.x - second.x).hypot(first.y - second.y);
+                match self.phase {
+                    Phase::TwoFingers {
+                        centroid: last,
+                        start_spread,
+                    } => {
+                        gestures.wheel = [
+                            (centroid[0] - last[0]) * self.scroll_speed,
+                            (centroid[1] - last[1]) * self.scroll_speed,
+                        ];
+                        if start_spread > 0. {
+                            gestures.pinch = Some(spread / start_spread);
+                        }
+                        self.phase = Phase::TwoFingers {
+                            centroid,
+                            start_spread,
+                        };
+                    }
+                    _ => {
+                        gestures.pinch = Some(1.);
+                        self.phase = Phase::TwoFingers {
+                            centroid,
+                            start_spread: spread,
+                        };
+                    }
+                }
+                gestures.pointer = Some(centroid);
+            }
+        }
+
+        if gestures.pointer.is_some() {
+            self.last_pointer = gestures.pointer;
+        }
+        gestures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds one frame of `fingers`, 0.1 seconds after the last.
+    fn update(recognizer: &mut GestureRecognizer, fingers: &[[f32; 2]]) -> Gestures {
+        let points: Vec<_> = fingers
+            .iter()
+            .enumerate()
+            .map(|(id, [x, y])| TouchPoint {
+                id: id as u8,
+                x: *x,
+                y: *y,
+            })
+            .collect();
+        recognizer.update(&points, 0.1)
+    }
+
+    fn buttons(gestures: Gestures) -> (bool, bool) {
+        (gestures.left_down, gestures.right_down)
+    }
+
+    #[test]
+    fn tap_presses_on_lift_and_releases_next_frame() {
+        let mut recognizer = GestureRecognizer::new();
+        assert_eq!(
+            buttons(update(&mut recognizer, &[[0.5, 0.5]])),
+            (false, false)
+        );
+
+        let lift = update(&mut recognizer, &[]);
+        assert_eq!(buttons(lift), (true, false));
+        assert_eq!(lift.pointer, Some([0.5, 0.5]));
+        assert_eq!(buttons(update(&mut recognizer, &[])), (false, false));
+    }
+
+    #[test]
+    fn long_press_holds_the_right_button_only() {
+        let mut recognizer = GestureRecognizer::new().long_press_time(0.25);
+        for _ in 0..3 {
+            assert_eq!(
+                buttons(update(&mut recognizer, &[[0.5, 0.5]])),
+                (false, false)
+            );
+        }
+        for _ in 0..2 {
+            assert_eq!(
+                buttons(update(&mut recognizer, &[[0.51, 0.5]])),
+                (false, true)
+            );
+        }
+        assert_eq!(buttons(update(&mut recognizer, &[])), (false, false));
+        assert_eq!(buttons(update(&mut recognizer, &[])), (false, false));
+    }
+
+    #[test]
+    fn drag_presses_where_the_finger_landed() {
+        let mut recognizer = GestureRecognizer::new();
+        update(&mut recognizer, &[[0.5, 0.5]]);
+
+        let start = update(&mut recognizer, &[[0.6, 0.5]]);
+        assert_eq!(buttons(start), (true, false));
+        assert_eq!(start.pointer, Some([0.5, 0.5]));
+        let moved = update(&mut recognizer, &[[0.7, 0.5]]);
+        assert_eq!(buttons(moved), (true, false));
+        assert_eq!(moved.pointer, Some([0.7, 0.5]));
+        assert_eq!(buttons(update(&mut recognizer, &[])), (false, false));
+    }
+
+    #[test]
+    fn two_fingers_scroll_and_pinch() {
+        let mut recognizer = GestureRecognizer::new().scroll_speed(10.);
+        let first = update(&mut recognizer, &[[0.4, 0.5], [0.6, 0.5]]);
+        assert_eq!(first.wheel, [0., 0.]);
+        assert_eq!(first.pinch, Some(1.));
+        assert_eq!(buttons(first), (false, false));
+
+        // Both fingers move down while spreading to twice the distance.
+        let second = update(&mut recognizer, &[[0.3, 0.6], [0.7, 0.6]]);
+        assert!(second.wheel[0].abs() < 1e-5, "{:?}", second.wheel);
+        assert!((second.wheel[1] - 1.).abs() < 1e-5, "{:?}", second.wheel);
+        assert!((second.pinch.unwrap() - 2.).abs() < 1e-5);
+        assert_eq!(buttons(second), (false, false));
+    }
+
+    #[test]
+    fn fingers_left_from_two_finger_gestures_are_ignored() {
+        let mut recognizer = GestureRecognizer::new().long_press_time(0.25);
+        update(&mut recognizer, &[[0.4, 0.5], [0.6, 0.5]]);
+        for x in [0.4, 0.6, 0.6, 0.6] {
+            let gestures = update(&mut recognizer, &[[x, 0.5]]);
+            assert_eq!(buttons(gestures), (false, false));
+            assert_eq!(gestures.pinch, None);
+        }
+        // Lifting the last finger is not a tap, but the next finger is.
+        assert_eq!(buttons(update(&mut recognizer, &[])), (false, false));
+        update(&mut recognizer, &[[0.5, 0.5]]);
+        assert_eq!(buttons(update(&mut recognizer, &[])), (true, false));
+    }
+}
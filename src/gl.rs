@@ -0,0 +1,67 @@
+//! The subset of GL types and enums used by the renderer, kept target independent so the
+//! backends can be implemented off-device.
+
+pub type GLenum = u32;
+pub type GLint = i32;
+pub type GLuint = u32;
+pub type GLsizei = i32;
+
+pub const GL_FALSE: u32 = 0;
+pub const GL_TRUE: u32 = 1;
+
+pub const GL_ZERO: GLenum = 0;
+pub const GL_ONE: GLenum = 1;
+pub const GL_TRIANGLES: GLenum = 0x0004;
+
+pub const GL_NO_ERROR: GLenum = 0;
+pub const GL_INVALID_ENUM: GLenum = 0x0500;
+pub const GL_INVALID_VALUE: GLenum = 0x0501;
+pub const GL_INVALID_OPERATION: GLenum = 0x0502;
+
+pub const GL_FRONT: GLenum = 0x0404;
+pub const GL_BACK: GLenum = 0x0405;
+pub const GL_FRONT_AND_BACK: GLenum = 0x0408;
+
+pub const GL_POLYGON_MODE: GLenum = 0x0B40;
+pub const GL_CULL_FACE: GLenum = 0x0B44;
+pub const GL_DEPTH_TEST: GLenum = 0x0B71;
+pub const GL_VIEWPORT: GLenum = 0x0BA2;
+pub const GL_BLEND_DST: GLenum = 0x0BE0;
+pub const GL_BLEND_SRC: GLenum = 0x0BE1;
+pub const GL_BLEND: GLenum = 0x0BE2;
+pub const GL_SCISSOR_BOX: GLenum = 0x0C10;
+pub const GL_SCISSOR_TEST: GLenum = 0x0C11;
+pub const GL_TEXTURE_2D: GLenum = 0x0DE1;
+
+pub const GL_SRC_ALPHA: GLenum = 0x0302;
+pub const GL_ONE_MINUS_SRC_ALPHA: GLenum = 0x0303;
+
+pub const GL_UNSIGNED_BYTE: GLenum = 0x1401;
+pub const GL_UNSIGNED_SHORT: GLenum = 0x1403;
+pub const GL_UNSIGNED_INT: GLenum = 0x1405;
+pub const GL_FLOAT: GLenum = 0x1406;
+
+pub const GL_MODELVIEW: GLenum = 0x1700;
+pub const GL_PROJECTION: GLenum = 0x1701;
+
+pub const GL_ALPHA: GLenum = 0x1906;
+pub const GL_RGBA: GLenum = 0x1908;
+pub const GL_FILL: GLenum = 0x1B02;
+
+pub const GL_NEAREST: GLenum = 0x2600;
+pub const GL_LINEAR: GLenum = 0x2601;
+pub const GL_NEAREST_MIPMAP_NEAREST: GLenum = 0x2700;
+pub const GL_LINEAR_MIPMAP_LINEAR: GLenum = 0x2703;
+pub const GL_TEXTURE_MAG_FILTER: GLenum = 0x2800;
+pub const GL_TEXTURE_MIN_FILTER: GLenum = 0x2801;
+
+pub const GL_TEXTURE_BINDING_2D: GLenum = 0x8069;
+pub const GL_VERTEX_ARRAY: GLenum = 0x8074;
+pub const GL_COLOR_ARRAY: GLenum = 0x8076;
+pub const GL_TEXTURE_COORD_ARRAY: GLenum = 0x8078;
+
+pub const GL_FRAGMENT_SHADER: GLenum = 0x8B30;
+pub const GL_VERTEX_SHADER: GLenum = 0x8B31;
+pub const GL_COMPILE_STATUS: GLenum = 0x8B81;
+pub const GL_LINK_STATUS: GLenum = 0x8B82;
+pub const GL_CURRENT_PROGRAM: GLenum = 0x8B8D;
@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+
+use imgui::sys::{ImFont, ImFontAtlas, ImFontAtlas_ClearTexData, ImFontConfig};
+use imgui::{FontAtlas, FontConfig, FontId, FontSource};
+
+/// Codepoints per glyph page.
+pub const GLYPH_PAGE_SIZE: u32 = 256;
+
+const MAX_PAGES: u32 = char::MAX as u32 / GLYPH_PAGE_SIZE + 1;
+/// Longest zero terminated range list. Adjacent pages share a range, so at most every other
+/// page starts one.
+const MAX_RANGES_LEN: usize = (MAX_PAGES.div_ceil(2) * 2 + 1) as usize;
+
+/// Keeps the font atlas down to the glyph pages the app recently drew, so a CJK font fits in
+/// texture memory. Dear ImGui samples a single atlas texture, so pages are swapped by rebuilding
+/// the atlas with the resident pages only; `ImguiRenderer::prepare_frame` then re-uploads it.
+///
+/// Adds a single font to the atlas and leaves the app's other fonts alone. The atlas owns the
+/// only copy of the font data, with the glyph ranges stored after it, so either side can be
+/// dropped first. Clearing the atlas removes the font for good.
+pub struct GlyphPages {
+    /// The font data until the first `update` moves it into the atlas.
+    data: Vec<u8>,
+    size_pixels: f32,
+    max_pages: usize,
+    /// Resident pages and the frame they were last requested in. Page 0 is never evicted.
+    pages: BTreeMap<u32, u64>,
+    frame: u64,
+    dirty: bool,
+    /// Offset of the glyph ranges in the atlas' copy of the font data.
+    ranges_offset: usize,
+    font: Option<FontId>,
+}
+
+impl GlyphPages {
+    /// `data` is a TTF/OTF font, rasterized at `size_pixels`.
+    pub fn new(data: Vec<u8>, size_pixels: f32) -> Self {
+        Self {
+            ranges_offset: data.len().next_multiple_of(std::mem::align_of::<u32>()),
+            data,
+            size_pixels,
+            max_pages: 16,
+            pages: BTreeMap::from([(0, 0)]),
+            frame: 0,
+            dirty: true,
+            font: None,
+        }
+    }
+
+    /// Pages kept resident once older ones can be evicted. Pages requested in the last frame
+    /// are always kept, even past this budget.
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    /// The font built from the resident pages, once `update` has run and until the atlas is
+    /// cleared.
+    pub fn font(&self) -> Option<FontId> {
+        self.font
+    }
+
+    pub fn resident_pages(&self) -> impl Iterator<Item = u32> + '_ {
+        self.pages.keys().copied()
+    }
+
+    /// Marks the pages of `text` as used this frame. Glyphs of pages that are not resident yet
+    /// draw as the fallback glyph until the next `update`.
+    pub fn request(&mut self, text: &str) {
+        for c in text.chars() {
+            let page = c as u32 / GLYPH_PAGE_SIZE;
+            if self.pages.insert(page, self.frame).is_none() {
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Call before `ImguiRenderer::prepare_frame`. Rebuilds the atlas fonts if a new page was
+    /// requested, evicting the least recently used pages over the budget, and returns whether
+    /// it did.
+    pub fn update(&mut self, fonts: &mut FontAtlas) -> bool {
+        let rebuild = self.dirty;
+        if rebuild {
+            self.evict();
+            self.build(fonts);
+        }
+        self.frame += 1;
+        rebuild
+    }
+
+    fn evict(&mut self) {
+        while self.pages.len() > self.max_pages {
+            let oldest = self
+                .pages
+                .iter()
+                .filter(|(page, frame)| **page != 0 && **frame < self.frame)
+                .min_by_key(|(_, frame)| **frame)
+                .map(|(page, _)| *page);
+            match oldest {
+                Some(page) => self.pages.remove(&page),
+                None => break,
+            };
+        }
+    }
+
+    /// The zero terminated glyph ranges of the resident pages, adjacent pages merged.
+    fn glyph_ranges(&self) -> Vec<u32> {
+        let mut ranges: Vec<u32> = Vec::new();
+        for page in self.pages.keys() {
+            let start = (page * GLYPH_PAGE_SIZE).max(1);
+            let end = page * GLYPH_PAGE_SIZE + GLYPH_PAGE_SIZE - 1;
+            match ranges.last_mut() {
+                Some(last) if *last + 1 == start => *last = end,
+                _ => ranges.extend([start, end]),
+            }
+        }
+        ranges.push(0);
+        ranges
+    }
+
+    fn build(&mut self, fonts: &mut FontAtlas) {
+        let ranges = self.glyph_ranges();
+        debug_assert!(ranges.len() <= MAX_RANGES_LEN);
+
+        if self.font.is_none() && !self.data.is_empty() {
+            let mut data = std::mem::take(&mut self.data);
+            data.resize(self.font_data_size(), 0);
+            self.font = Some(fonts.add_font(&[FontSource::TtfData {
+                data: &data,
+                size_pixels: self.size_pixels,
+                config: Some(FontConfig::default()),
+            }]));
+        }
+
+        match self.font_config(fonts) {
+            // Safety: the atlas is not locked, as `update` runs before `new_frame`, and the
+            // config's font data is ours, sized to hold `MAX_RANGES_LEN` ranges at
+            // `ranges_offset`.
+            Some(config) => unsafe {
+                let glyph_ranges = (*config).FontData.cast::<u8>().add(self.ranges_offset);
+                let glyph_ranges = glyph_ranges.cast::<u32>();
+                glyph_ranges.copy_from_nonoverlapping(ranges.as_ptr(), ranges.len());
+                (*config).GlyphRanges = glyph_ranges;
+
+                // Rebuild on the next texture request, as `ImFontAtlas::AddFont` does.
+                let atlas = fonts as *mut FontAtlas as *mut ImFontAtlas;
+                (*atlas).TexReady = false;
+                ImFontAtlas_ClearTexData(atlas);
+            },
+            None => self.font = None,
+        }
+        self.dirty = false;
+    }
+
+    /// Size of the atlas' copy of the font data: the font, then room for the glyph ranges.
+    fn font_data_size(&self) -> usize {
+        self.ranges_offset + MAX_RANGES_LEN * std::mem::size_of::<u32>()
+    }
+
+    /// The atlas config of `font`, unless the atlas was cleared since it was added.
+    fn font_config(&self, fonts: &mut FontAtlas) -> Option<*mut ImFontConfig> {
+        let font = self.font?;
+        let atlas = fonts as *mut FontAtlas as *mut ImFontAtlas;
+        // Safety: `FontAtlas` is a transparent view of `ImFontAtlas`. Empty vectors may have a
+        // null `Data`, which `FontAtlas::get_font` does not expect.
+        let configs = unsafe {
+            let configs = &(*atlas).ConfigData;
+            if (*atlas).Fonts.Size == 0 || configs.Size == 0 {
+                return None;
+            }
+            std::slice::from_raw_parts_mut(configs.Data, configs.Size as usize)
+        };
+        let font = fonts.get_font(font)? as *const _ as *mut ImFont;
+        configs
+            .iter_mut()
+            .find(|config| {
+                config.DstFont == font && config.FontDataSize as usize == self.font_data_size()
+            })
+            .map(|config| config as *mut _)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::MutexGuard;
+
+    use super::*;
+
+    /// A fresh context, with the imgui lock held for it. Drop the context before the lock.
+    fn context() -> (MutexGuard<'static, ()>, imgui::Context) {
+        let lock = crate::lock_imgui();
+        let mut ctx = imgui::Context::create();
+        ctx.set_ini_filename(None);
+        (lock, ctx)
+    }
+
+    /// Runs a frame that requests `text`.
+    fn frame(pages: &mut GlyphPages, fonts: &mut FontAtlas, text: &str) -> Vec<u32> {
+        pages.request(text);
+        pages.update(fonts);
+        pages.resident_pages().collect()
+    }
+
+    #[test]
+    fn least_recently_used_pages_are_evicted() {
+        let (_lock, mut ctx) = context();
+        let mut pages = GlyphPages::new(vec![0; 101], 16.).max_pages(3);
+        assert!(pages.update(ctx.fonts()));
+
+        assert_eq!(frame(&mut pages, ctx.fonts(), "\u{100}"), [0, 1]);
+        assert_eq!(frame(&mut pages, ctx.fonts(), "\u{200}"), [0, 1, 2]);
+        assert_eq!(frame(&mut pages, ctx.fonts(), "\u{100}\u{300}"), [0, 1, 3]);
+        // Pages requested this frame stay past the budget.
+        assert_eq!(
+            frame(&mut pages, ctx.fonts(), "\u{400}\u{500}\u{600}"),
+            [0, 4, 5, 6]
+        );
+        // Resident pages do not rebuild the atlas.
+        pages.request("\u{400}");
+        assert!(!pages.update(ctx.fonts()));
+    }
+
+    #[test]
+    fn page_zero_is_never_evicted() {
+        let (_lock, mut ctx) = context();
+        let mut pages = GlyphPages::new(vec![0; 101], 16.).max_pages(1);
+        pages.update(ctx.fonts());
+
+        assert_eq!(frame(&mut pages, ctx.fonts(), "\u{100}"), [0, 1]);
+        assert_eq!(frame(&mut pages, ctx.fonts(), "\u{200}"), [0, 2]);
+    }
+
+    #[test]
+    fn adjacent_pages_share_a_range() {
+        let (_lock, mut ctx) = context();
+        let app_font = ctx
+            .fonts()
+            .add_font(&[FontSource::DefaultFontData { config: None }]);
+        let mut pages = GlyphPages::new(vec![0; 101], 16.);
+        pages.request("\u{100}\u{300}");
+        pages.update(ctx.fonts());
+
+        let ranges = [1, 0x1ff, 0x300, 0x3ff, 0];
+        assert_eq!(pages.glyph_ranges(), ranges);
+        let config = pages.font_config(ctx.fonts()).unwrap();
+        let atlas_ranges = unsafe { std::slice::from_raw_parts((*config).GlyphRanges, 5) };
+        assert_eq!(atlas_ranges, ranges);
+
+        // The atlas holds the only copy of the font, and the app's font is still there.
+        assert!(pages.data.is_empty());
+        assert_eq!(ctx.fonts().fonts(), [app_font, pages.font().unwrap()]);
+        assert!(!ctx.fonts().is_built());
+    }
+
+    #[test]
+    fn clearing_the_atlas_drops_the_font() {
+        let (_lock, mut ctx) = context();
+        let mut pages = GlyphPages::new(vec![0; 101], 16.);
+        pages.update(ctx.fonts());
+        ctx.fonts().clear();
+
+        pages.request("\u{100}");
+        pages.update(ctx.fonts());
+        assert_eq!(pages.font(), None);
+    }
+}
@@ -1,548 +1,788 @@
-#![feature(offset_of)]
-
-use std::ffi::c_void;
-use std::mem::{offset_of, zeroed};
-
-use bindings::c_ImGui_ImplVitaGL_InitTouch;
-use imgui::sys::*;
-use vitagl_sys::*;
-use vitasdk_sys::psp2::kernel::processmgr::sceKernelGetProcessTimeWide;
-use vitasdk_sys::psp2::{ctrl::*, touch::*};
-use vitasdk_sys::psp2common::ctrl::SceCtrlButtons::*;
-use vitasdk_sys::psp2common::ctrl::*;
-
-use crate::bindings::c_ImGui_ImplVitaGL_PollTouch;
-
-pub struct ImguiRenderer {
-    imgui_mempool_size: usize,
-    start_vertex: *mut f32,
-    start_texcoord: *mut f32,
-    start_color: *mut u8,
-    vertex_buffer: *mut f32,
-    texcoord_buffer: *mut f32,
-    color_buffer: *mut u8,
-    index_buffer: *mut u16,
-    font_texture: Option<std::ffi::c_uint>,
-    touch_usage: bool,
-    gamepad_usage: bool,
-    g_time: u64,
-    mx: i32,
-    my: i32,
-    mouse_pressed: [i32; 3],
-    hires_x: i32,
-    hires_y: i32,
-    counter: usize,
+use std::mem::offset_of;
+
+use imgui::internal::RawWrapper;
+use imgui::{
+    BackendFlags, DrawCmd, DrawCmdParams, DrawData, DrawIdx, DrawVert, FontAtlas, TextureId,
+};
+
+pub mod backend;
+mod buffer;
+pub mod builder;
+pub mod error;
+pub mod gestures;
+pub mod gl;
+pub mod glyphs;
+pub mod platform;
+mod registration;
+mod ring;
+pub mod shader;
+pub mod software;
+pub mod textures;
+#[cfg(target_os = "vita")]
+pub mod vita;
+
+use backend::*;
+use buffer::MemBuffer;
+pub use builder::{ConfigError, ImguiRendererBuilder};
+pub use error::RendererError;
+pub use gestures::{GestureRecognizer, Gestures};
+use gl::*;
+pub use glyphs::GlyphPages;
+pub use platform::{RearTouch, VitaPlatform, VitaPlatformBuilder};
+use registration::{Registration, RENDERER_NAME};
+use ring::VertexRing;
+pub use ring::{FrameAdvance, OverflowPolicy};
+pub use shader::{Pipeline, ShaderSource};
+use shader::{ShaderProgram, ATTRIB_COLOR, ATTRIB_POSITION, ATTRIB_TEXCOORD};
+pub use textures::{Texture, TextureData, TextureFilter, Textures};
+#[cfg(target_os = "vita")]
+use vita::VitaGl;
+
+/// Serializes the unit tests that create an imgui context, as imgui has a single current
+/// context per process. A test that failed while holding the lock does not fail the others.
+#[cfg(test)]
+pub(crate) fn lock_imgui() -> std::sync::MutexGuard<'static, ()> {
+    static IMGUI_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    IMGUI_LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
 }
 
+/// Capabilities `render` changes and puts back afterwards.
+const RESTORED_CAPABILITIES: [GLenum; 5] = [
+    GL_BLEND,
+    GL_CULL_FACE,
+    GL_DEPTH_TEST,
+    GL_SCISSOR_TEST,
+    GL_TEXTURE_2D,
+];
+
+/// How `render` feeds ImGui's vertex data to the GPU.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DrawMode {
+    /// Copies the vertices of every index into the mapped vertex pool and draws them with
+    /// `vglDrawObjects` over the identity index buffer.
+    #[default]
+    Deindexed,
+    /// Points interleaved client arrays at each `ImDrawList`'s `ImDrawVert`s once and draws every
+    /// command with `glDrawElements` on the list's own index buffer.
+    Indexed,
+}
+
+/// Pixel format of the uploaded font atlas texture.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FontAtlasFormat {
+    /// `GetTexDataAsAlpha8`, uploaded as `GL_ALPHA`. Glyphs take their color from the vertices.
+    #[default]
+    Alpha8,
+    /// `GetTexDataAsRGBA32`, uploaded as `GL_RGBA`, for colored custom rects and glyphs.
+    Rgba32,
+}
+
+pub struct ImguiRenderer<
+    #[cfg(target_os = "vita")] G: GlBackend = VitaGl,
+    #[cfg(not(target_os = "vita"))] G: GlBackend,
+> {
+    gl: G,
+    vertices: VertexRing,
+    index_buffer: MemBuffer<u16>,
+    textures: Textures,
+    font_texture: Option<TextureId>,
+    font_atlas_format: FontAtlasFormat,
+    texture_filter: TextureFilter,
+    mipmaps: bool,
+    frame_advance: FrameAdvance,
+    draw_mode: DrawMode,
+    pipeline: Pipeline,
+    program: Option<ShaderProgram>,
+    registration: Registration,
+}
+
+#[cfg(target_os = "vita")]
 impl ImguiRenderer {
-    pub fn new() -> Self {
-        unsafe {
-            sceTouchSetSamplingState(
-                SceTouchPortType::SCE_TOUCH_PORT_FRONT,
-                SceTouchSamplingState::SCE_TOUCH_SAMPLING_STATE_START,
-            );
-            sceCtrlSetSamplingMode(SceCtrlPadInputMode::SCE_CTRL_MODE_ANALOG_WIDE);
-
-            let io = &mut *imgui::sys::igGetIO();
-            io.MouseDrawCursor = false;
-
-            let imgui_mempool_size = 0x200000;
-            let vertex_buffer =
-                imgui::sys::igMemAlloc(std::mem::size_of::<f32>() * imgui_mempool_size * 3)
-                    as *mut f32;
-            let texcoord_buffer =
-                imgui::sys::igMemAlloc(std::mem::size_of::<f32>() * imgui_mempool_size * 2)
-                    as *mut f32;
-            let color_buffer: *mut u8 =
-                imgui::sys::igMemAlloc(std::mem::size_of::<u8>() * imgui_mempool_size * 4)
-                    as *mut u8;
-            let index_buffer =
-                imgui::sys::igMemAlloc(std::mem::size_of::<u16>() * 0xF000) as *mut u16;
-
-            for i in 0..0xF000 {
-                *index_buffer.add(i) = i as u16;
-            }
+    pub fn new(ctx: &mut imgui::Context) -> Self {
+        Self::try_new(ctx).unwrap()
+    }
 
-            io.ClipboardUserData = std::ptr::null_mut();
-            c_ImGui_ImplVitaGL_InitTouch();
-
-            Self {
-                imgui_mempool_size,
-                start_color: color_buffer,
-                start_texcoord: texcoord_buffer,
-                start_vertex: vertex_buffer,
-                color_buffer,
-                texcoord_buffer,
-                vertex_buffer,
-                index_buffer,
-                font_texture: None,
-                touch_usage: true,
-                gamepad_usage: true,
-                g_time: 0,
-                mx: 0,
-                my: 0,
-                mouse_pressed: [0; 3],
-                hires_x: 0,
-                hires_y: 0,
-                counter: 0,
-            }
+    pub fn try_new(ctx: &mut imgui::Context) -> Result<Self, RendererError> {
+        Self::try_with_backend(ctx, VitaGl)
+    }
+
+    pub fn builder() -> ImguiRendererBuilder {
+        ImguiRendererBuilder::new()
+    }
+}
+
+impl<G: GlBackend> ImguiRenderer<G> {
+    pub fn with_backend(ctx: &mut imgui::Context, gl: G) -> Self {
+        Self::try_with_backend(ctx, gl).unwrap()
+    }
+
+    pub fn try_with_backend(ctx: &mut imgui::Context, gl: G) -> Result<Self, RendererError> {
+        ImguiRendererBuilder::default().build_with_backend(ctx, gl)
+    }
+
+    pub(crate) fn from_builder(
+        builder: ImguiRendererBuilder,
+        ctx: &mut imgui::Context,
+        gl: G,
+    ) -> Result<Self, RendererError> {
+        let vertices = VertexRing::new(
+            builder.mempool_size,
+            builder.frames_in_flight,
+            builder.overflow_policy,
+        )?;
+
+        let mut index_buffer = MemBuffer::<u16>::alloc(builder.index_buffer_len)?;
+        for i in 0..index_buffer.len() {
+            unsafe { *index_buffer.as_mut_ptr().add(i) = i as u16 };
         }
+
+        let mut renderer = Self {
+            gl,
+            vertices,
+            index_buffer,
+            textures: Textures::default(),
+            font_texture: None,
+            font_atlas_format: builder.font_atlas_format,
+            texture_filter: builder.texture_filter,
+            mipmaps: builder.mipmaps,
+            frame_advance: builder.frame_advance,
+            draw_mode: builder.draw_mode,
+            pipeline: builder.pipeline,
+            program: None,
+            registration: Registration::new(ctx),
+        };
+
+        renderer.create_device_objects(ctx.fonts())?;
+
+        ctx.set_renderer_name(RENDERER_NAME.to_owned());
+        // Draw lists past 64k vertices then come as several commands with a `VtxOffset`
+        // instead of failing with 16-bit indices.
+        ctx.io_mut()
+            .backend_flags
+            .insert(BackendFlags::RENDERER_HAS_VTX_OFFSET);
+
+        Ok(renderer)
     }
 
-    fn create_device_objects(&mut self) {
-        unsafe {
-            let io = &mut *imgui::sys::igGetIO();
-
-            let mut pixels = std::ptr::null_mut();
-            let mut width = 0;
-            let mut height = 0;
-            let mut bytes_per_pixel = 0;
-            imgui::sys::ImFontAtlas_GetTexDataAsAlpha8(
-                io.Fonts,
-                &mut pixels,
-                &mut width,
-                &mut height,
-                &mut bytes_per_pixel,
-            );
+    pub fn gl(&self) -> &G {
+        &self.gl
+    }
 
-            let mut last_texture = 0;
-            glGetIntegerv(GL_TEXTURE_BINDING_2D, &mut last_texture);
-
-            let mut new_texture = 0;
-            glGenTextures(1, &mut new_texture);
-            glBindTexture(GL_TEXTURE_2D, new_texture);
-            self.font_texture = Some(new_texture);
-
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR as i32);
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR as i32);
-            //glPixelStorei(GL_UNPACK_ROW_LENGTH, 0);
-            glTexImage2D(
-                GL_TEXTURE_2D,
-                0,
-                GL_ALPHA as i32,
-                width,
-                height,
-                0,
-                GL_ALPHA as u32,
-                GL_UNSIGNED_BYTE,
-                pixels as *const _,
-            );
+    pub fn gl_mut(&mut self) -> &mut G {
+        &mut self.gl
+    }
 
-            imgui::sys::ImFontAtlas_SetTexID(
-                io.Fonts,
-                self.font_texture.unwrap() as usize as *mut _,
-            );
+    pub fn pipeline(&self) -> &Pipeline {
+        &self.pipeline
+    }
+
+    pub fn font_atlas_format(&self) -> FontAtlasFormat {
+        self.font_atlas_format
+    }
+
+    pub fn texture_filter(&self) -> TextureFilter {
+        self.texture_filter
+    }
 
-            glBindTexture(GL_TEXTURE_2D, last_texture as u32);
+    pub fn mipmaps(&self) -> bool {
+        self.mipmaps
+    }
+
+    /// Changes the filter and mipmapping of the font atlas and every user texture.
+    pub fn set_texture_filter(
+        &mut self,
+        texture_filter: TextureFilter,
+        mipmaps: bool,
+    ) -> Result<(), RendererError> {
+        self.texture_filter = texture_filter;
+        self.mipmaps = mipmaps;
+
+        self.clear_gl_errors();
+        let mut last_texture = [0];
+        self.gl
+            .get_integerv(GL_TEXTURE_BINDING_2D, &mut last_texture);
+
+        let names: Vec<_> = self
+            .textures
+            .iter()
+            .map(|(_, texture)| texture.name)
+            .collect();
+        for name in names {
+            self.gl.bind_texture(GL_TEXTURE_2D, name);
+            self.set_texture_parameters();
+        }
+        let error = self.gl.get_error();
+
+        self.gl.bind_texture(GL_TEXTURE_2D, last_texture[0] as u32);
+
+        if error != GL_NO_ERROR {
+            return Err(RendererError::Gl(error));
         }
+        Ok(())
     }
 
-    fn invalidate_device_objects(&mut self) {
-        let font_texture = self.font_texture.take();
-        if let Some(font_texture) = font_texture {
-            unsafe {
-                glDeleteTextures(1, &font_texture);
+    pub fn frame_advance(&self) -> FrameAdvance {
+        self.frame_advance
+    }
 
-                let io = &mut *imgui::sys::igGetIO();
-                imgui::sys::ImFontAtlas_SetTexID(io.Fonts, std::ptr::null_mut());
-            }
+    /// With `FrameAdvance::Manual`, call once per buffer swap after the frame's last `render`.
+    /// Later `render` calls then write vertices to the next region. Does nothing with
+    /// `FrameAdvance::Render`.
+    pub fn end_frame(&mut self) {
+        if self.frame_advance == FrameAdvance::Manual {
+            self.vertices.begin_frame();
         }
     }
-}
 
-impl ImguiRenderer {
-    pub fn new_frame(&mut self) {
-        if self.font_texture.is_none() {
-            self.create_device_objects();
+    pub fn draw_mode(&self) -> DrawMode {
+        self.draw_mode
+    }
+
+    pub fn set_draw_mode(&mut self, draw_mode: DrawMode) {
+        self.draw_mode = draw_mode;
+    }
+
+    pub fn textures(&self) -> &Textures {
+        &self.textures
+    }
+
+    /// Uploads a texture that ImGui widgets can draw with the returned id.
+    pub fn insert_texture(
+        &mut self,
+        width: u32,
+        height: u32,
+        data: TextureData,
+    ) -> Result<TextureId, RendererError> {
+        let name = self.gl.gen_texture();
+        if let Err(error) = self.upload_texture(name, width, height, data) {
+            self.gl.delete_texture(name);
+            return Err(error);
         }
+        Ok(self.textures.insert(Texture {
+            name,
+            width,
+            height,
+        }))
+    }
 
-        unsafe {
-            let io = &mut *imgui::sys::igGetIO();
+    /// Replaces the pixels of a texture, possibly changing its size.
+    pub fn update_texture(
+        &mut self,
+        id: TextureId,
+        width: u32,
+        height: u32,
+        data: TextureData,
+    ) -> Result<(), RendererError> {
+        let name = match self.textures.get(id) {
+            Some(texture) => texture.name,
+            None => return Err(RendererError::UnknownTexture(id)),
+        };
+        self.upload_texture(name, width, height, data)?;
+
+        let texture = self.textures.get_mut(id).unwrap();
+        texture.width = width;
+        texture.height = height;
+        Ok(())
+    }
 
-            let mut viewport: [i32; 4] = [0; 4];
-            glGetIntegerv(GL_VIEWPORT, viewport.as_mut_ptr());
-            let w = viewport[2];
-            let h = viewport[3];
+    /// Deletes a texture. Removing the font atlas texture makes the next `prepare_frame` upload
+    /// it again.
+    pub fn remove_texture(&mut self, id: TextureId) -> Result<(), RendererError> {
+        let texture = self
+            .textures
+            .remove(id)
+            .ok_or(RendererError::UnknownTexture(id))?;
+        self.gl.delete_texture(texture.name);
+        if self.font_texture == Some(id) {
+            self.font_texture = None;
+        }
+        Ok(())
+    }
 
-            io.DisplaySize = imgui::sys::ImVec2::new(w as f32, h as f32);
-            io.DisplayFramebufferScale = imgui::sys::ImVec2::new(1., 1.);
+    fn upload_texture(
+        &mut self,
+        name: GLuint,
+        width: u32,
+        height: u32,
+        data: TextureData,
+    ) -> Result<(), RendererError> {
+        let expected = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(data.bytes_per_pixel()))
+            .unwrap_or(usize::MAX);
+        if data.len() != expected {
+            return Err(RendererError::TextureData {
+                expected,
+                actual: data.len(),
+            });
+        }
+        let (format, pixels) = data.pixels();
+
+        self.clear_gl_errors();
+        let mut last_texture = [0];
+        self.gl
+            .get_integerv(GL_TEXTURE_BINDING_2D, &mut last_texture);
+
+        self.gl.bind_texture(GL_TEXTURE_2D, name);
+        //glPixelStorei(GL_UNPACK_ROW_LENGTH, 0);
+        self.gl.tex_image_2d(
+            GL_TEXTURE_2D,
+            0,
+            format as i32,
+            width as i32,
+            height as i32,
+            format,
+            GL_UNSIGNED_BYTE,
+            &pixels,
+        );
+        self.set_texture_parameters();
+        let error = self.gl.get_error();
 
-            const FREQUENCY: usize = 1000000;
-            let current_time = sceKernelGetProcessTimeWide();
-            io.DeltaTime = if self.g_time > 0 {
-                (current_time - self.g_time) as f32 / FREQUENCY as f32
-            } else {
-                1.0 / 60.0
-            };
-            self.g_time = current_time;
-
-            if self.touch_usage {
-                let scale_x = 960.0 / io.DisplaySize.x;
-                let scale_y = 544.0 / io.DisplaySize.y;
-                let offset_x = 0.;
-                let offset_y = 0.;
-                c_ImGui_ImplVitaGL_PollTouch(
-                    offset_x,
-                    offset_y,
-                    scale_x as f64,
-                    scale_y as f64,
-                    &mut self.mx,
-                    &mut self.my,
-                    self.mouse_pressed.as_mut_ptr(),
-                );
-            }
+        self.gl.bind_texture(GL_TEXTURE_2D, last_texture[0] as u32);
 
-            if self.gamepad_usage {
-                let mut pad = zeroed::<SceCtrlData>();
-                let mut lstick_x = 0;
-                let mut lstick_y = 0;
-                self.poll_left_stick(&mut pad, &mut lstick_x, &mut lstick_y);
-
-                io.NavInputs[ImGuiNavInput_Activate as usize] = if pad.buttons & SCE_CTRL_CROSS != 0
-                {
-                    1.
-                } else {
-                    0.
-                };
-                io.NavInputs[ImGuiNavInput_Cancel as usize] = if pad.buttons & SCE_CTRL_CIRCLE != 0
-                {
-                    1.
-                } else {
-                    0.
-                };
-                io.NavInputs[ImGuiNavInput_Input as usize] = if pad.buttons & SCE_CTRL_TRIANGLE != 0
-                {
-                    1.
-                } else {
-                    0.
-                };
-                io.NavInputs[ImGuiNavInput_Menu as usize] = if pad.buttons & SCE_CTRL_SQUARE != 0 {
-                    1.
-                } else {
-                    0.
-                };
-                io.NavInputs[ImGuiNavInput_DpadLeft as usize] = if pad.buttons & SCE_CTRL_LEFT != 0
-                {
-                    1.
-                } else {
-                    0.
-                };
-                io.NavInputs[ImGuiNavInput_DpadRight as usize] =
-                    if pad.buttons & SCE_CTRL_RIGHT != 0 {
-                        1.
-                    } else {
-                        0.
-                    };
-                io.NavInputs[ImGuiNavInput_DpadUp as usize] = if pad.buttons & SCE_CTRL_UP != 0 {
-                    1.
-                } else {
-                    0.
-                };
-                io.NavInputs[ImGuiNavInput_DpadDown as usize] = if pad.buttons & SCE_CTRL_DOWN != 0
-                {
-                    1.
-                } else {
-                    0.
-                };
-
-                // if !self.mousestick_usage || io.NavInputs[ImGuiNavInput_Menu] == 1.0f {
-                io.NavInputs[ImGuiNavInput_FocusPrev as usize] =
-                    if pad.buttons & SCE_CTRL_LTRIGGER != 0 {
-                        1.
-                    } else {
-                        0.
-                    };
-                io.NavInputs[ImGuiNavInput_FocusNext as usize] =
-                    if pad.buttons & SCE_CTRL_RTRIGGER != 0 {
-                        1.
-                    } else {
-                        0.
-                    };
-                if lstick_x < 0 {
-                    io.NavInputs[ImGuiNavInput_LStickLeft as usize] = -lstick_x as f32 / 16.
-                };
-                if lstick_x > 0 {
-                    io.NavInputs[ImGuiNavInput_LStickRight as usize] = lstick_x as f32 / 16.
-                };
-                if lstick_y < 0 {
-                    io.NavInputs[ImGuiNavInput_LStickUp as usize] = -lstick_y as f32 / 16.
-                };
-                if lstick_y > 0 {
-                    io.NavInputs[ImGuiNavInput_LStickDown as usize] = lstick_y as f32 / 16.
-                };
-                // }
-            }
+        if error != GL_NO_ERROR {
+            return Err(RendererError::Gl(error));
+        }
+        Ok(())
+    }
+
+    /// Call before `Context::new_frame`. Reloads the font texture when the atlas was cleared,
+    /// gained fonts or had its texture id reset since the last upload.
+    pub fn prepare_frame(&mut self, fonts: &mut FontAtlas) -> Result<(), RendererError> {
+        if !fonts.is_built() || Some(fonts.tex_id) != self.font_texture {
+            self.reload_font_texture(fonts)?;
+        }
+        Ok(())
+    }
 
-            // Keys for mouse emulation
-            /*if (mousestick_usage && !(io.NavInputs[ImGuiNavInput_Menu] == 1.0f)){
-                SceCtrlData pad;
-                ImGui_ImplVitaGL_PollLeftStick(&pad, &mx, &my);
-                if ((pad.buttons & SCE_CTRL_LTRIGGER) != (g_OldPad.buttons & SCE_CTRL_LTRIGGER))
-                    g_MousePressed[0] = pad.buttons & SCE_CTRL_LTRIGGER;
-                if ((pad.buttons & SCE_CTRL_RTRIGGER) != (g_OldPad.buttons & SCE_CTRL_RTRIGGER))
-                    g_MousePressed[1] = pad.buttons & SCE_CTRL_RTRIGGER;
-                g_OldPad = pad;
+    /// Rebuilds the font atlas and re-uploads its texture, e.g. after adding glyph ranges.
+    pub fn reload_font_texture(&mut self, fonts: &mut FontAtlas) -> Result<(), RendererError> {
+        let texture;
+        let data = match self.font_atlas_format {
+            FontAtlasFormat::Alpha8 => {
+                texture = fonts.build_alpha8_texture();
+                TextureData::Alpha(texture.data)
             }
-            */
-
-            // Setup mouse inputs (we already got mouse wheel, keyboard keys & characters from our event handler)
-            //Uint32 mouse_buttons = SDL_GetMouseState(&mx, &my);
-            io.MousePos = imgui::sys::ImVec2::new(std::f32::MIN, std::f32::MIN);
-            io.MouseDown[0] = self.mouse_pressed[0] != 0;
-            io.MouseDown[1] = self.mouse_pressed[1] != 0;
-            io.MouseDown[2] = self.mouse_pressed[2] != 0;
-
-            if self.mx < 0 {
-                self.mx = 0;
-            } else if self.mx > 960 {
-                self.mx = 960;
+            FontAtlasFormat::Rgba32 => {
+                texture = fonts.build_rgba32_texture();
+                TextureData::Rgba(texture.data)
             }
-            if self.my < 0 {
-                self.my = 0;
-            } else if self.my > 544 {
-                self.my = 544;
+        };
+        let font_texture = match self.font_texture {
+            Some(font_texture) => {
+                self.update_texture(font_texture, texture.width, texture.height, data)?;
+                font_texture
             }
+            None => {
+                let font_texture = self.insert_texture(texture.width, texture.height, data)?;
+                self.font_texture = Some(font_texture);
+                font_texture
+            }
+        };
 
-            io.MousePos = imgui::sys::ImVec2::new(self.mx as f32, self.my as f32);
+        fonts.tex_id = font_texture;
+        Ok(())
+    }
 
-            // imgui::sys::igNewFrame();
-            vglIndexPointerMapped(self.index_buffer as *const _);
+    /// Discards errors left by earlier GL calls, so only those raised after it are reported.
+    fn clear_gl_errors(&mut self) {
+        while self.gl.get_error() != GL_NO_ERROR {}
+    }
+
+    /// Applies the texture filter to the bound texture, generating its mipmaps if enabled.
+    fn set_texture_parameters(&mut self) {
+        let (min_filter, mag_filter) = self.texture_filter.gl_filters(self.mipmaps);
+        self.gl
+            .tex_parameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, min_filter as i32);
+        self.gl
+            .tex_parameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, mag_filter as i32);
+        if self.mipmaps {
+            self.gl.generate_mipmap(GL_TEXTURE_2D);
         }
     }
 
-    pub fn poll_left_stick(&mut self, pad: *mut SceCtrlData, x: *mut i32, y: *mut i32) {
-        unsafe {
-            sceCtrlPeekBufferPositive(0, pad, 1);
-            let mut lx = ((*pad).lx as i32 - 127) * 256;
-            let mut ly = ((*pad).ly as i32 - 127) * 256;
-            rescale_analog(&mut lx, &mut ly, 7680);
-            self.hires_x += lx;
-            self.hires_y += ly;
-            if self.hires_x != 0 || self.hires_y != 0 {
-                let slowdown = 2048;
-                *x += self.hires_x / slowdown;
-                *y += self.hires_y / slowdown;
-                self.hires_x %= slowdown;
-                self.hires_y %= slowdown;
+    fn create_device_objects(&mut self, fonts: &mut FontAtlas) -> Result<(), RendererError> {
+        self.reload_font_texture(fonts)?;
+
+        if let Pipeline::Shaders(source) = &self.pipeline {
+            match ShaderProgram::new(&mut self.gl, source) {
+                Ok(program) => self.program = Some(program),
+                Err(error) => {
+                    self.invalidate_device_objects();
+                    return Err(error);
+                }
             }
         }
+        Ok(())
     }
 
-    pub fn render(&mut self) {
-        unsafe {
-            imgui::sys::igRender();
-            let draw_data = imgui::sys::igGetDrawData();
-
-            let io = &mut *imgui::sys::igGetIO();
-            let fb_width = (io.DisplaySize.x * io.DisplayFramebufferScale.x) as i32;
-            let fb_height = (io.DisplaySize.y * io.DisplayFramebufferScale.y) as i32;
-            if fb_width == 0 || fb_height == 0 {
-                return;
+    fn invalidate_device_objects(&mut self) {
+        if let Some(program) = self.program.take() {
+            program.delete(&mut self.gl);
+        }
+
+        if let Some(font_texture) = self.font_texture.take() {
+            let _ = self.remove_texture(font_texture);
+        }
+    }
+}
+
+impl<G: GlBackend> ImguiRenderer<G> {
+    pub fn render(&mut self, draw_data: &DrawData) -> Result<(), RendererError> {
+        let fb_width = (draw_data.display_size[0] * draw_data.framebuffer_scale[0]) as i32;
+        let fb_height = (draw_data.display_size[1] * draw_data.framebuffer_scale[1]) as i32;
+        if fb_width <= 0 || fb_height <= 0 {
+            return Ok(());
+        }
+        if self.frame_advance == FrameAdvance::Render {
+            self.vertices.begin_frame();
+        }
+
+        let mut last_texture = [0];
+        self.gl
+            .get_integerv(GL_TEXTURE_BINDING_2D, &mut last_texture);
+
+        let mut last_polygon_mode = [0; 2];
+        self.gl
+            .get_integerv(GL_POLYGON_MODE, &mut last_polygon_mode);
+        let mut last_viewport = [0; 4];
+        self.gl.get_integerv(GL_VIEWPORT, &mut last_viewport);
+        let mut last_scissor_box = [0; 4];
+        self.gl.get_integerv(GL_SCISSOR_BOX, &mut last_scissor_box);
+
+        let mut last_program = [0];
+        self.gl.get_integerv(GL_CURRENT_PROGRAM, &mut last_program);
+        let mut last_blend_src = [0];
+        self.gl.get_integerv(GL_BLEND_SRC, &mut last_blend_src);
+        let mut last_blend_dst = [0];
+        self.gl.get_integerv(GL_BLEND_DST, &mut last_blend_dst);
+        let last_capabilities = RESTORED_CAPABILITIES.map(|cap| (cap, self.gl.is_enabled(cap)));
+
+        if self.program.is_none() {
+            self.gl.matrix_mode(GL_PROJECTION);
+            self.gl.push_matrix();
+            self.gl.matrix_mode(GL_MODELVIEW);
+            self.gl.push_matrix();
+        }
+
+        self.setup_render_state(draw_data, fb_width, fb_height);
+        let result = unsafe { self.render_draw_lists(draw_data, fb_width, fb_height) };
+
+        if self.program.is_some() {
+            self.gl.disable_vertex_attrib_array(ATTRIB_COLOR);
+            self.gl.disable_vertex_attrib_array(ATTRIB_TEXCOORD);
+            self.gl.disable_vertex_attrib_array(ATTRIB_POSITION);
+        } else {
+            self.gl.disable_client_state(GL_COLOR_ARRAY);
+            self.gl.disable_client_state(GL_TEXTURE_COORD_ARRAY);
+            self.gl.disable_client_state(GL_VERTEX_ARRAY);
+            self.gl.matrix_mode(GL_MODELVIEW);
+            self.gl.pop_matrix();
+            self.gl.matrix_mode(GL_PROJECTION);
+            self.gl.pop_matrix();
+        }
+        self.gl.use_program(last_program[0] as u32);
+        self.gl.bind_texture(GL_TEXTURE_2D, last_texture[0] as u32);
+        for (cap, enabled) in last_capabilities {
+            if enabled {
+                self.gl.enable(cap);
+            } else {
+                self.gl.disable(cap);
             }
+        }
+        self.gl
+            .blend_func(last_blend_src[0] as u32, last_blend_dst[0] as u32);
+        self.gl.polygon_mode(GL_FRONT, last_polygon_mode[0] as u32);
+        self.gl.polygon_mode(GL_BACK, last_polygon_mode[1] as u32);
+        self.gl.viewport(
+            last_viewport[0],
+            last_viewport[1],
+            last_viewport[2],
+            last_viewport[3],
+        );
+        self.gl.scissor(
+            last_scissor_box[0],
+            last_scissor_box[1],
+            last_scissor_box[2],
+            last_scissor_box[3],
+        );
+
+        result
+    }
 
-            imgui::sys::ImDrawData_ScaleClipRects(draw_data, io.DisplayFramebufferScale);
-
-            let mut last_texture = 0;
-            glGetIntegerv(GL_TEXTURE_BINDING_2D, &mut last_texture);
-
-            let mut last_polygon_mode = [0; 2];
-            glGetIntegerv(GL_POLYGON_MODE, last_polygon_mode.as_mut_ptr());
-            let mut last_viewport = [0; 4];
-            glGetIntegerv(GL_VIEWPORT, last_viewport.as_mut_ptr());
-            let mut last_scissor_box = [0; 4];
-            glGetIntegerv(GL_SCISSOR_BOX, last_scissor_box.as_mut_ptr());
-
-            glUseProgram(0);
-            glEnable(GL_BLEND);
-            glBlendFunc(GL_SRC_ALPHA, GL_ONE_MINUS_SRC_ALPHA);
-            glDisable(GL_CULL_FACE);
-            glDisable(GL_DEPTH_TEST);
-            glEnable(GL_SCISSOR_TEST);
-            glEnableClientState(GL_VERTEX_ARRAY);
-            glEnableClientState(GL_TEXTURE_COORD_ARRAY);
-            glEnableClientState(GL_COLOR_ARRAY);
-            glEnable(GL_TEXTURE_2D);
-            glPolygonMode(GL_FRONT_AND_BACK, GL_FILL);
-
-            glViewport(0, 0, fb_width, fb_height);
-            glMatrixMode(GL_PROJECTION);
-            glPushMatrix();
-            glLoadIdentity();
-            glOrtho(
-                0.,
-                io.DisplaySize.x as f64,
-                io.DisplaySize.y as f64,
-                0.,
-                0.,
-                1.,
+    fn setup_render_state(&mut self, draw_data: &DrawData, fb_width: i32, fb_height: i32) {
+        self.gl.enable(GL_BLEND);
+        self.gl.blend_func(GL_SRC_ALPHA, GL_ONE_MINUS_SRC_ALPHA);
+        self.gl.disable(GL_CULL_FACE);
+        self.gl.disable(GL_DEPTH_TEST);
+        self.gl.enable(GL_SCISSOR_TEST);
+        self.gl.polygon_mode(GL_FRONT_AND_BACK, GL_FILL);
+        self.gl.viewport(0, 0, fb_width, fb_height);
+
+        let [left, top] = draw_data.display_pos;
+        let right = left + draw_data.display_size[0];
+        let bottom = top + draw_data.display_size[1];
+        if let Some(program) = &self.program {
+            let (program, proj_mtx, texture) = (program.program, program.proj_mtx, program.texture);
+            self.gl.use_program(program);
+            self.gl.uniform1i(texture, 0);
+            self.gl.uniform_matrix4fv(
+                proj_mtx,
+                &shader::ortho_projection(draw_data.display_pos, draw_data.display_size),
             );
-            glMatrixMode(GL_MODELVIEW);
-            glPushMatrix();
-            glLoadIdentity();
-
-            let count = (*draw_data).CmdListsCount;
-
-            for n in 0..count {
-                let cmd_list = &mut **(*draw_data).CmdLists.add(n as usize);
-                let vtx_buffer = cmd_list.VtxBuffer.Data as *mut u8;
-                let mut idx_buffer = cmd_list.IdxBuffer.Data;
-
-                for cmd_i in 0..cmd_list.CmdBuffer.Size {
-                    let pcmd = &mut *cmd_list.CmdBuffer.Data.add(cmd_i as usize);
-                    if pcmd.UserCallback.is_some() {
-                        (pcmd.UserCallback.as_ref().unwrap())(cmd_list, pcmd);
-                    } else {
-                        glBindTexture(GL_TEXTURE_2D, pcmd.TextureId as u32);
-                        glScissor(
-                            pcmd.ClipRect.x as i32,
-                            (fb_height as f32 - pcmd.ClipRect.w) as i32,
-                            (pcmd.ClipRect.z - pcmd.ClipRect.x) as i32,
-                            (pcmd.ClipRect.w - pcmd.ClipRect.y) as i32,
-                        );
+            self.gl.enable_vertex_attrib_array(ATTRIB_POSITION);
+            self.gl.enable_vertex_attrib_array(ATTRIB_TEXCOORD);
+            self.gl.enable_vertex_attrib_array(ATTRIB_COLOR);
+        } else {
+            self.gl.use_program(0);
+            self.gl.enable_client_state(GL_VERTEX_ARRAY);
+            self.gl.enable_client_state(GL_TEXTURE_COORD_ARRAY);
+            self.gl.enable_client_state(GL_COLOR_ARRAY);
+            self.gl.enable(GL_TEXTURE_2D);
+
+            self.gl.matrix_mode(GL_PROJECTION);
+            self.gl.load_identity();
+            self.gl
+                .ortho(left as f64, right as f64, bottom as f64, top as f64, 0., 1.);
+            self.gl.matrix_mode(GL_MODELVIEW);
+            self.gl.load_identity();
+        }
+
+        unsafe {
+            self.gl
+                .index_pointer_mapped(self.index_buffer.as_ptr() as *const _)
+        };
+    }
 
-                        let vp = self.vertex_buffer;
-                        let tp = self.texcoord_buffer;
-                        let cp = self.color_buffer;
-                        let indices = idx_buffer;
-                        for idx in 0..pcmd.ElemCount {
-                            let index = *(indices.add(idx as usize));
-                            let vertices = vtx_buffer.add(
-                                offset_of!(ImDrawVert, pos)
-                                    + std::mem::size_of::<ImDrawVert>() * index as usize,
-                            ) as *const _ as *const f32;
-                            let texcoords = vtx_buffer.add(
-                                offset_of!(ImDrawVert, uv)
-                                    + std::mem::size_of::<ImDrawVert>() * index as usize,
-                            ) as *const _ as *const f32;
-                            let colors = vtx_buffer.add(
-                                offset_of!(ImDrawVert, col)
-                                    + std::mem::size_of::<ImDrawVert>() * index as usize,
-                            ) as *const _ as *const u8;
-
-                            *self.vertex_buffer = *vertices.add(0);
-                            *self.vertex_buffer.add(1) = *vertices.add(1);
-                            *self.vertex_buffer.add(2) = 0.;
-                            *self.texcoord_buffer.add(0) = *texcoords.add(0);
-                            *self.texcoord_buffer.add(1) = *texcoords.add(1);
-                            *self.color_buffer.add(0) = *colors.add(0);
-                            *self.color_buffer.add(1) = *colors.add(1);
-                            *self.color_buffer.add(2) = *colors.add(2);
-                            *self.color_buffer.add(3) = *colors.add(3);
-                            self.vertex_buffer = self.vertex_buffer.add(3);
-                            self.texcoord_buffer = self.texcoord_buffer.add(2);
-                            self.color_buffer = self.color_buffer.add(4);
+    unsafe fn render_draw_lists(
+        &mut self,
+        draw_data: &DrawData,
+        fb_width: i32,
+        fb_height: i32,
+    ) -> Result<(), RendererError> {
+        let clip_off = draw_data.display_pos;
+        let clip_scale = draw_data.framebuffer_scale;
+        for draw_list in draw_data.draw_lists() {
+            let vtx_buffer = draw_list.vtx_buffer().as_ptr() as *const u8;
+            let idx_buffer = draw_list.idx_buffer().as_ptr();
+            let mut last_vtx_offset = None;
+
+            for command in draw_list.commands() {
+                match command {
+                    DrawCmd::Elements {
+                        count,
+                        cmd_params:
+                            DrawCmdParams {
+                                clip_rect,
+                                texture_id,
+                                vtx_offset,
+                                idx_offset,
+                            },
+                    } => {
+                        // Clip to the framebuffer and skip commands clipped away entirely, as
+                        // GL rejects a negative scissor size.
+                        let clip_min = [
+                            ((clip_rect[0] - clip_off[0]) * clip_scale[0]).max(0.),
+                            ((clip_rect[1] - clip_off[1]) * clip_scale[1]).max(0.),
+                        ];
+                        let clip_max = [
+                            ((clip_rect[2] - clip_off[0]) * clip_scale[0]).min(fb_width as f32),
+                            ((clip_rect[3] - clip_off[1]) * clip_scale[1]).min(fb_height as f32),
+                        ];
+                        if clip_max[0] <= clip_min[0] || clip_max[1] <= clip_min[1] {
+                            continue;
                         }
 
-                        if false
-                        /*shaders_usage*/
-                        {
-                            vglVertexAttribPointerMapped(0, vp as *const _);
-                            vglVertexAttribPointerMapped(1, tp as *const _);
-                            vglVertexAttribPointerMapped(2, cp as *const _);
-                        } else {
-                            vglVertexPointerMapped(3, vp as *const _);
-                            vglTexCoordPointerMapped(tp as *const _);
-                            vglColorPointerMapped(GL_UNSIGNED_BYTE, cp as *const _);
+                        let texture = self
+                            .textures
+                            .get(texture_id)
+                            .ok_or(RendererError::UnknownTexture(texture_id))?;
+                        self.gl.bind_texture(GL_TEXTURE_2D, texture.name);
+                        self.gl.scissor(
+                            clip_min[0] as i32,
+                            (fb_height as f32 - clip_max[1]) as i32,
+                            (clip_max[0] - clip_min[0]) as i32,
+                            (clip_max[1] - clip_min[1]) as i32,
+                        );
+
+                        let vtx_buffer =
+                            vtx_buffer.add(vtx_offset * std::mem::size_of::<DrawVert>());
+                        let idx_buffer = idx_buffer.add(idx_offset);
+                        match self.draw_mode {
+                            DrawMode::Deindexed => {
+                                self.draw_deindexed(vtx_buffer, idx_buffer, count)?
+                            }
+                            DrawMode::Indexed => {
+                                if last_vtx_offset != Some(vtx_offset) {
+                                    self.set_vertex_pointers(vtx_buffer);
+                                    last_vtx_offset = Some(vtx_offset);
+                                }
+                                self.draw_indexed(idx_buffer, count as u32)
+                            }
                         }
-                        vglDrawObjects(GL_TRIANGLES, pcmd.ElemCount as i32, GL_TRUE as u8);
                     }
-
-                    idx_buffer = idx_buffer.add(pcmd.ElemCount as usize);
-                    self.counter += pcmd.ElemCount as usize;
-                    if self.counter > self.imgui_mempool_size - 0x66700 {
-                        self.vertex_buffer = self.start_vertex;
-                        self.color_buffer = self.start_color;
-                        self.texcoord_buffer = self.start_texcoord;
-                        self.counter = 0;
+                    DrawCmd::ResetRenderState => {
+                        self.setup_render_state(draw_data, fb_width, fb_height);
+                        last_vtx_offset = None;
+                    }
+                    DrawCmd::RawCallback { callback, raw_cmd } => {
+                        callback(draw_list.raw(), raw_cmd);
+                        // The callback may have pointed the arrays elsewhere.
+                        last_vtx_offset = None;
                     }
                 }
             }
+        }
+
+        Ok(())
+    }
 
-            glDisableClientState(GL_COLOR_ARRAY);
-            glDisableClientState(GL_TEXTURE_COORD_ARRAY);
-            glDisableClientState(GL_VERTEX_ARRAY);
-            glBindTexture(GL_TEXTURE_2D, last_texture as u32);
-            glMatrixMode(GL_MODELVIEW);
-            glPopMatrix();
-            glMatrixMode(GL_PROJECTION);
-            glPopMatrix();
-            glPolygonMode(GL_FRONT, last_polygon_mode[0] as u32);
-            glPolygonMode(GL_BACK, last_polygon_mode[1] as u32);
-            glViewport(
-                last_viewport[0],
-                last_viewport[1],
-                last_viewport[2],
-                last_viewport[3],
+    /// Points the vertex arrays of indexed draws at `vtx_buffer`'s `DrawVert`s.
+    unsafe fn set_vertex_pointers(&mut self, vtx_buffer: *const u8) {
+        let stride = std::mem::size_of::<DrawVert>() as i32;
+        if self.program.is_some() {
+            self.gl.vertex_attrib_pointer(
+                ATTRIB_POSITION,
+                2,
+                GL_FLOAT,
+                false,
+                stride,
+                vtx_buffer.add(offset_of!(DrawVert, pos)) as *const _,
+            );
+            self.gl.vertex_attrib_pointer(
+                ATTRIB_TEXCOORD,
+                2,
+                GL_FLOAT,
+                false,
+                stride,
+                vtx_buffer.add(offset_of!(DrawVert, uv)) as *const _,
+            );
+            self.gl.vertex_attrib_pointer(
+                ATTRIB_COLOR,
+                4,
+                GL_UNSIGNED_BYTE,
+                true,
+                stride,
+                vtx_buffer.add(offset_of!(DrawVert, col)) as *const _,
+            );
+        } else {
+            self.gl.vertex_pointer(
+                2,
+                GL_FLOAT,
+                stride,
+                vtx_buffer.add(offset_of!(DrawVert, pos)) as *const _,
             );
-            glScissor(
-                last_scissor_box[0],
-                last_scissor_box[1],
-                last_scissor_box[2],
-                last_scissor_box[3],
+            self.gl.tex_coord_pointer(
+                2,
+                GL_FLOAT,
+                stride,
+                vtx_buffer.add(offset_of!(DrawVert, uv)) as *const _,
+            );
+            self.gl.color_pointer(
+                4,
+                GL_UNSIGNED_BYTE,
+                stride,
+                vtx_buffer.add(offset_of!(DrawVert, col)) as *const _,
             );
         }
     }
-}
 
-impl Drop for ImguiRenderer {
-    fn drop(&mut self) {
-        unsafe {
-            imgui::sys::igMemFree(self.color_buffer as *mut c_void);
-            imgui::sys::igMemFree(self.texcoord_buffer as *mut c_void);
-            imgui::sys::igMemFree(self.vertex_buffer as *mut c_void);
-            imgui::sys::igMemFree(self.index_buffer as *mut c_void);
+    /// Draws `count` indices in chunks of whole triangles the identity index buffer covers.
+    unsafe fn draw_deindexed(
+        &mut self,
+        vtx_buffer: *const u8,
+        indices: *const DrawIdx,
+        count: usize,
+    ) -> Result<(), RendererError> {
+        let max_chunk = self.index_buffer.len() / 3 * 3;
+        let mut drawn = 0;
+        while drawn < count {
+            let chunk = (count - drawn).min(max_chunk);
+            self.draw_deindexed_chunk(vtx_buffer, indices.add(drawn), chunk)?;
+            drawn += chunk;
         }
-
-        self.invalidate_device_objects();
+        Ok(())
     }
-}
 
-#[allow(dead_code)]
-mod bindings {
-    extern "C" {
-        pub fn c_ImGui_ImplVitaGL_InitTouch();
-        pub fn c_ImGui_ImplVitaGL_PollTouch(
-            x0: std::ffi::c_double,
-            y0: std::ffi::c_double,
-            sx: std::ffi::c_double,
-            sy: std::ffi::c_double,
-            mx: *mut std::ffi::c_int,
-            my: *mut std::ffi::c_int,
-            mbuttons: *mut std::ffi::c_int,
-        );
+    unsafe fn draw_deindexed_chunk(
+        &mut self,
+        vtx_buffer: *const u8,
+        indices: *const DrawIdx,
+        count: usize,
+    ) -> Result<(), RendererError> {
+        let slots = self.vertices.alloc(count)?;
+        let mut vp = slots.vertex;
+        let mut tp = slots.texcoord;
+        let mut cp = slots.color;
+        for idx in 0..count {
+            let index = *(indices.add(idx));
+            let vertices = vtx_buffer
+                .add(offset_of!(DrawVert, pos) + std::mem::size_of::<DrawVert>() * index as usize)
+                as *const f32;
+            let texcoords = vtx_buffer
+                .add(offset_of!(DrawVert, uv) + std::mem::size_of::<DrawVert>() * index as usize)
+                as *const f32;
+            let colors = vtx_buffer
+                .add(offset_of!(DrawVert, col) + std::mem::size_of::<DrawVert>() * index as usize);
+
+            *vp = *vertices.add(0);
+            *vp.add(1) = *vertices.add(1);
+            *vp.add(2) = 0.;
+            *tp.add(0) = *texcoords.add(0);
+            *tp.add(1) = *texcoords.add(1);
+            *cp.add(0) = *colors.add(0);
+            *cp.add(1) = *colors.add(1);
+            *cp.add(2) = *colors.add(2);
+            *cp.add(3) = *colors.add(3);
+            vp = vp.add(3);
+            tp = tp.add(2);
+            cp = cp.add(4);
+        }
+
+        let (vp, tp, cp) = (slots.vertex, slots.texcoord, slots.color);
+
+        if self.program.is_some() {
+            self.gl
+                .vertex_attrib_pointer_mapped(ATTRIB_POSITION, vp as *const _);
+            self.gl
+                .vertex_attrib_pointer_mapped(ATTRIB_TEXCOORD, tp as *const _);
+            self.gl
+                .vertex_attrib_pointer_mapped(ATTRIB_COLOR, cp as *const _);
+        } else {
+            self.gl.vertex_pointer_mapped(3, vp as *const _);
+            self.gl.tex_coord_pointer_mapped(tp as *const _);
+            self.gl
+                .color_pointer_mapped(GL_UNSIGNED_BYTE, cp as *const _);
+        }
+        self.gl
+            .draw_objects(GL_TRIANGLES, count as i32, self.program.is_none());
+
+        Ok(())
     }
-}
 
-fn rescale_analog(x: &mut i32, y: &mut i32, dead: i32) {
-    let analog_x = *x as f32;
-    let analog_y = *y as f32;
-    let dead_zone = dead as f32;
-    let maximum = 32768.;
-    let magnitude = (analog_x * analog_x + analog_y * analog_y).sqrt();
-    if magnitude >= dead_zone {
-        let scaling_factor = maximum / magnitude * (magnitude - dead_zone) / (maximum - dead_zone);
-        *x = (analog_x * scaling_factor) as i32;
-        *y = (analog_y * scaling_factor) as i32;
-    } else {
-        *x = 0;
-        *y = 0;
+    unsafe fn draw_indexed(&mut self, indices: *const DrawIdx, count: u32) {
+        let index_type = if std::mem::size_of::<DrawIdx>() == 2 {
+            GL_UNSIGNED_SHORT
+        } else {
+            GL_UNSIGNED_INT
+        };
+        self.gl
+            .draw_elements(GL_TRIANGLES, count as i32, index_type, indices as *const _);
     }
 }
 
-#[link(name = "vitaGL", kind = "static")]
-extern "C" {}
-
-#[link(name = "vitashark", kind = "static")]
-extern "C" {}
-
-#[link(name = "SceShaccCg_stub", kind = "static")]
-extern "C" {}
-
-#[link(name = "SceShaccCgExt", kind = "static")]
-extern "C" {}
-
-#[link(name = "taihen_stub", kind = "static")]
-extern "C" {}
+impl<G: GlBackend> Drop for ImguiRenderer<G> {
+    fn drop(&mut self) {
+        self.invalidate_device_objects();
+        for texture in self.textures.drain() {
+            self.gl.delete_texture(texture.name);
+        }
 
-#[link(name = "mathneon", kind = "static")]
-extern "C" {}
+        if let Some(io) = self.registration.io_mut() {
+            io.BackendRendererName = std::ptr::null();
+            io.BackendFlags &= !(BackendFlags::RENDERER_HAS_VTX_OFFSET.bits() as i32);
+        }
+    }
+}
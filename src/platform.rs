@@ -0,0 +1,791 @@
+use imgui::{BackendFlags, ConfigFlags, Io, Key};
+
+use crate::backend::*;
+use crate::builder::ConfigError;
+use crate::gestures::{GestureRecognizer, Gestures};
+use crate::registration::{Registration, PLATFORM_NAME};
+#[cfg(target_os = "vita")]
+use crate::vita::{VitaClock, VitaInput};
+
+/// Controller buttons and the ImGui gamepad keys they report. The face buttons follow
+/// ImGui's PlayStation layout: cross activates, circle cancels.
+const GAMEPAD_BUTTONS: [(u32, Key); 16] = [
+    (SCE_CTRL_START, Key::GamepadStart),
+    (SCE_CTRL_SELECT, Key::GamepadBack),
+    (SCE_CTRL_SQUARE, Key::GamepadFaceLeft),
+    (SCE_CTRL_CIRCLE, Key::GamepadFaceRight),
+    (SCE_CTRL_TRIANGLE, Key::GamepadFaceUp),
+    (SCE_CTRL_CROSS, Key::GamepadFaceDown),
+    (SCE_CTRL_LEFT, Key::GamepadDpadLeft),
+    (SCE_CTRL_RIGHT, Key::GamepadDpadRight),
+    (SCE_CTRL_UP, Key::GamepadDpadUp),
+    (SCE_CTRL_DOWN, Key::GamepadDpadDown),
+    (SCE_CTRL_LTRIGGER, Key::GamepadL1),
+    (SCE_CTRL_RTRIGGER, Key::GamepadR1),
+    // The extra shoulder buttons of a PS TV controller.
+    (SCE_CTRL_L1, Key::GamepadL2),
+    (SCE_CTRL_R1, Key::GamepadR2),
+    (SCE_CTRL_L3, Key::GamepadL3),
+    (SCE_CTRL_R3, Key::GamepadR3),
+];
+
+/// Screen resolution touch positions are scaled to before the viewport is applied.
+const SCREEN_SIZE: [f32; 2] = [960., 544.];
+
+/// Delta time of the first frame and of the frame after a suspend.
+const NOMINAL_DELTA_TIME: f32 = 1. / 60.;
+
+/// Shortest delta time reported. ImGui asserts on a zero delta, which a coarse or fixed clock
+/// can report.
+const MIN_DELTA_TIME: f32 = 1e-6;
+
+/// Raw stick reading at rest. Full travel is 127 steps either way.
+const STICK_CENTER: u8 = 128;
+
+/// Stick deflection, as a fraction of full travel, ignored around the center.
+const STICK_DEAD_ZONE: f32 = 0.25;
+
+/// What fingers on the rear touch panel do.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RearTouch {
+    #[default]
+    Off,
+    /// Dragging scrolls: one finger vertically, two fingers in both directions.
+    Scroll,
+    /// The first finger moves the mouse, holding a second finger presses the left button.
+    Pointer,
+}
+
+/// Feeds display size, frame time, touch and gamepad input into ImGui. Independent of the
+/// renderer, so an app with its own input stack can skip it.
+pub struct VitaPlatform<
+    #[cfg(target_os = "vita")] I: InputBackend = VitaInput,
+    #[cfg(not(target_os = "vita"))] I: InputBackend,
+    #[cfg(target_os = "vita")] C: Clock = VitaClock,
+    #[cfg(not(target_os = "vita"))] C: Clock,
+> {
+    input: I,
+    clock: C,
+    display_size: [f32; 2],
+    viewport: [f32; 4],
+    framebuffer_scale: [f32; 2],
+    touch_usage: bool,
+    gestures: Option<GestureRecognizer>,
+    last_gestures: Gestures,
+    gamepad_usage: bool,
+    mousestick_usage: bool,
+    mousestick_speed: f32,
+    mousestick_acceleration: f32,
+    /// Trigger buttons held for the stick mouse last frame.
+    mousestick_buttons: u32,
+    /// Whether `MouseDrawCursor` was last set for the stick mouse or the rear pointer.
+    draw_cursor: bool,
+    rear_touch: RearTouch,
+    rear_scroll_speed: f32,
+    /// Finger count and centroid of the last rear panel sample, if touched.
+    last_rear: Option<(usize, [f32; 2])>,
+    max_delta_time: f32,
+    suspend_threshold: f32,
+    g_time: u64,
+    resumed: bool,
+    mx: i32,
+    my: i32,
+    mouse_pressed: [i32; 3],
+    /// Stick mouse motion not yet moved, below a pixel.
+    hires_x: f32,
+    hires_y: f32,
+    registration: Registration,
+}
+
+/// Construction options for `VitaPlatform`. The defaults match `VitaPlatform::init`.
+#[derive(Clone, Debug)]
+pub struct VitaPlatformBuilder {
+    display_size: [f32; 2],
+    viewport: [f32; 4],
+    framebuffer_scale: [f32; 2],
+    touch_ports: TouchPorts,
+    ctrl_mode: CtrlMode,
+    touch_usage: bool,
+    gestures: Option<GestureRecognizer>,
+    gamepad_usage: bool,
+    mousestick_usage: bool,
+    mousestick_speed: f32,
+    mousestick_acceleration: f32,
+    rear_touch: RearTouch,
+    rear_scroll_speed: f32,
+    max_delta_time: f32,
+    suspend_threshold: f32,
+}
+
+impl Default for VitaPlatformBuilder {
+    fn default() -> Self {
+        Self {
+            display_size: [960., 544.],
+            viewport: [0., 0., 960., 544.],
+            framebuffer_scale: [1., 1.],
+            touch_ports: TouchPorts::Front,
+            ctrl_mode: CtrlMode::AnalogWide,
+            touch_usage: true,
+            gestures: None,
+            gamepad_usage: true,
+            mousestick_usage: false,
+            mousestick_speed: 500.,
+            mousestick_acceleration: 2.,
+            rear_touch: RearTouch::Off,
+            rear_scroll_speed: 10.,
+            max_delta_time: 0.1,
+            suspend_threshold: 1.,
+        }
+    }
+}
+
+impl VitaPlatformBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn display_size(mut self, display_size: [f32; 2]) -> Self {
+        self.display_size = display_size;
+        self
+    }
+
+    /// Screen rectangle the display is drawn into, in 960x544 screen pixels: x, y, width,
+    /// height. Touches are mapped through it, so a letterboxed or scaled display still lines up
+    /// with the fingers, and touches outside it are ignored. Defaults to the whole screen.
+    pub fn viewport(mut self, viewport: [f32; 4]) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Framebuffer pixels per display unit, reported as `DisplayFramebufferScale`.
+    pub fn framebuffer_scale(mut self, framebuffer_scale: [f32; 2]) -> Self {
+        self.framebuffer_scale = framebuffer_scale;
+        self
+    }
+
+    pub fn touch_ports(mut self, touch_ports: TouchPorts) -> Self {
+        self.touch_ports = touch_ports;
+        self
+    }
+
+    pub fn ctrl_mode(mut self, ctrl_mode: CtrlMode) -> Self {
+        self.ctrl_mode = ctrl_mode;
+        self
+    }
+
+    pub fn touch_usage(mut self, touch_usage: bool) -> Self {
+        self.touch_usage = touch_usage;
+        self
+    }
+
+    /// Reads the front panel through a gesture recognizer instead of as a plain pointer.
+    pub fn gestures(mut self, gestures: Option<GestureRecognizer>) -> Self {
+        self.gestures = gestures;
+        self
+    }
+
+    pub fn gamepad_usage(mut self, gamepad_usage: bool) -> Self {
+        self.gamepad_usage = gamepad_usage;
+        self
+    }
+
+    /// Moves the mouse with the left stick and clicks with the L and R triggers, which then no
+    /// longer navigate. Can be toggled later with `VitaPlatform::set_mousestick_usage`.
+    pub fn mousestick_usage(mut self, mousestick_usage: bool) -> Self {
+        self.mousestick_usage = mousestick_usage;
+        self
+    }
+
+    /// Stick mouse speed at full deflection, in display units per second.
+    pub fn mousestick_speed(mut self, mousestick_speed: f32) -> Self {
+        self.mousestick_speed = mousestick_speed;
+        self
+    }
+
+    /// Exponent of the stick mouse response to deflection, at least 1. 1 is linear, larger
+    /// values move slower near the center for precise pointing.
+    pub fn mousestick_acceleration(mut self, mousestick_acceleration: f32) -> Self {
+        self.mousestick_acceleration = mousestick_acceleration;
+        self
+    }
+
+    /// Needs `TouchPorts::Both`, or `TouchPorts::Back` with `touch_usage(false)`.
+    pub fn rear_touch(mut self, rear_touch: RearTouch) -> Self {
+        self.rear_touch = rear_touch;
+        self
+    }
+
+    /// Wheel steps of a drag across the whole rear panel. Negative values invert scrolling.
+    pub fn rear_scroll_speed(mut self, rear_scroll_speed: f32) -> Self {
+        self.rear_scroll_speed = rear_scroll_speed;
+        self
+    }
+
+    /// Longest `DeltaTime` reported, in seconds. Longer frames are clamped to it.
+    pub fn max_delta_time(mut self, max_delta_time: f32) -> Self {
+        self.max_delta_time = max_delta_time;
+        self
+    }
+
+    /// Gap between frames, in seconds, taken as a suspend and resume rather than a slow frame.
+    /// The frame after it gets a nominal 1/60 s delta.
+    pub fn suspend_threshold(mut self, suspend_threshold: f32) -> Self {
+        self.suspend_threshold = suspend_threshold;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self
+            .display_size
+            .iter()
+            .any(|size| !size.is_finite() || *size <= 0.)
+        {
+            return Err(ConfigError::DisplaySize(self.display_size));
+        }
+        if self.viewport.iter().any(|v| !v.is_finite())
+            || self.viewport[2] <= 0.
+            || self.viewport[3] <= 0.
+        {
+            return Err(ConfigError::Viewport(self.viewport));
+        }
+        if self
+            .framebuffer_scale
+            .iter()
+            .any(|scale| !scale.is_finite() || *scale <= 0.)
+        {
+            return Err(ConfigError::FramebufferScale(self.framebuffer_scale));
+        }
+        if !self.mousestick_speed.is_finite() || self.mousestick_speed < 0. {
+            return Err(ConfigError::MousestickSpeed(self.mousestick_speed));
+        }
+        if !self.mousestick_acceleration.is_finite() || self.mousestick_acceleration < 1. {
+            return Err(ConfigError::MousestickAcceleration(
+                self.mousestick_acceleration,
+            ));
+        }
+        if !self.max_delta_time.is_finite() || self.max_delta_time < MIN_DELTA_TIME {
+            return Err(ConfigError::DeltaTime(self.max_delta_time));
+        }
+        if !self.suspend_threshold.is_finite() || self.suspend_threshold < self.max_delta_time {
+            return Err(ConfigError::DeltaTime(self.suspend_threshold));
+        }
+        if self.touch_usage && !self.touch_ports.front() {
+            return Err(ConfigError::NoTouchPorts);
+        }
+        if self.rear_touch != RearTouch::Off && !self.touch_ports.back() {
+            return Err(ConfigError::NoTouchPorts);
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "vita")]
+    pub fn build(self, ctx: &mut imgui::Context) -> Result<VitaPlatform, ConfigError> {
+        self.build_with_backends(ctx, VitaInput, VitaClock)
+    }
+
+    pub fn build_with_backends<I: InputBackend, C: Clock>(
+        self,
+        ctx: &mut imgui::Context,
+        mut input: I,
+        clock: C,
+    ) -> Result<VitaPlatform<I, C>, ConfigError> {
+        self.validate()?;
+        Registration::check(ctx.platform_name())?;
+
+        input.init(self.touch_ports, self.ctrl_mode);
+        ctx.set_platform_name(PLATFORM_NAME.to_owned());
+        let io = ctx.io_mut();
+        // Nothing else shows where the stick mouse or the rear finger points. ImGui draws the
+        // cursor shape it asks for, so the platform honors cursor shapes while it does.
+        let draw_cursor = self.mousestick_usage || self.rear_touch == RearTouch::Pointer;
+        io.mouse_draw_cursor = draw_cursor;
+        io.backend_flags
+            .set(BackendFlags::HAS_MOUSE_CURSORS, draw_cursor);
+        if self.gamepad_usage {
+            io.config_flags |= ConfigFlags::NAV_ENABLE_GAMEPAD;
+            io.backend_flags |= BackendFlags::HAS_GAMEPAD;
+        }
+
+        Ok(VitaPlatform {
+            input,
+            clock,
+            display_size: self.display_size,
+            viewport: self.viewport,
+            framebuffer_scale: self.framebuffer_scale,
+            touch_usage: self.touch_usage,
+            gestures: self.gestures,
+            last_gestures: Gestures::default(),
+            gamepad_usage: self.gamepad_usage,
+            mousestick_usage: self.mousestick_usage,
+            mousestick_speed: self.mousestick_speed,
+            mousestick_acceleration: self.mousestick_acceleration,
+            mousestick_buttons: 0,
+            draw_cursor,
+            rear_touch: self.rear_touch,
+            rear_scroll_speed: self.rear_scroll_speed,
+            last_rear: None,
+            max_delta_time: self.max_delta_time,
+            suspend_threshold: self.suspend_threshold,
+            g_time: 0,
+            resumed: false,
+            mx: 0,
+            my: 0,
+            mouse_pressed: [0; 3],
+            hires_x: 0.,
+            hires_y: 0.,
+            registration: Registration::new(ctx),
+        })
+    }
+}
+
+#[cfg(target_os = "vita")]
+impl VitaPlatform {
+    pub fn init(ctx: &mut imgui::Context) -> Self {
+        VitaPlatformBuilder::default().build(ctx).unwrap()
+    }
+
+    pub fn builder() -> VitaPlatformBuilder {
+        VitaPlatformBuilder::new()
+    }
+}
+
+impl<I: InputBackend, C: Clock> VitaPlatform<I, C> {
+    pub fn input_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    pub fn clock_mut(&mut self) -> &mut C {
+        &mut self.clock
+    }
+
+    /// The front panel gestures of the last `prepare_frame`, including the pinch scale.
+    pub fn gestures(&self) -> &Gestures {
+        &self.last_gestures
+    }
+
+    pub fn mousestick_usage(&self) -> bool {
+        self.mousestick_usage
+    }
+
+    /// Turns the stick mouse on or off from the next `prepare_frame`, which also shows or hides
+    /// the software cursor.
+    pub fn set_mousestick_usage(&mut self, mousestick_usage: bool) {
+        self.mousestick_usage = mousestick_usage;
+    }
+
+    /// Whether the last `prepare_frame` followed a gap longer than the suspend threshold.
+    pub fn resumed(&self) -> bool {
+        self.resumed
+    }
+
+    /// Call before `Context::new_frame`.
+    pub fn prepare_frame(&mut self, io: &mut imgui::Io) {
+        io.display_size = self.display_size;
+        io.display_framebuffer_scale = self.framebuffer_scale;
+
+        const FREQUENCY: usize = 1000000;
+        let current_time = self.clock.now_micros();
+        let elapsed = current_time.saturating_sub(self.g_time) as f32 / FREQUENCY as f32;
+        self.resumed = self.g_time > 0 && elapsed > self.suspend_threshold;
+        io.delta_time = if self.g_time > 0 && !self.resumed {
+            elapsed.clamp(MIN_DELTA_TIME, self.max_delta_time)
+        } else {
+            NOMINAL_DELTA_TIME
+        };
+        self.g_time = current_time;
+
+        if self.touch_usage {
+            let touch = self.input.read_touch(TouchPanel::Front);
+            let touch = self.viewport_touches(touch);
+            if self.gestures.is_some() {
+                self.update_gestures(io, touch.points());
+            } else {
+                self.update_touch(touch.points());
+            }
+        }
+
+        if self.rear_touch != RearTouch::Off {
+            let mut touch = self.input.read_touch(TouchPanel::Back);
+            if self.rear_touch == RearTouch::Pointer {
+                touch = self.viewport_touches(touch);
+            }
+            self.update_rear_touch(io, touch.points());
+        }
+
+        let draw_cursor = self.mousestick_usage || self.rear_touch == RearTouch::Pointer;
+        if draw_cursor != self.draw_cursor {
+            io.mouse_draw_cursor = draw_cursor;
+            io.backend_flags
+                .set(BackendFlags::HAS_MOUSE_CURSORS, draw_cursor);
+            self.draw_cursor = draw_cursor;
+        }
+
+        let pad = if self.gamepad_usage || self.mousestick_usage {
+            Some(self.input.read_pad())
+        } else {
+            None
+        };
+
+        io.backend_flags
+            .set(BackendFlags::HAS_GAMEPAD, self.gamepad_usage);
+        if let Some(pad) = pad.as_ref().filter(|_| self.gamepad_usage) {
+            // The stick mouse takes the left stick and the triggers over from navigation.
+            let mouse_buttons = if self.mousestick_usage {
+                SCE_CTRL_LTRIGGER | SCE_CTRL_RTRIGGER
+            } else {
+                0
+            };
+            for (mask, key) in GAMEPAD_BUTTONS {
+                io.add_key_event(key, pad.buttons & mask & !mouse_buttons != 0);
+            }
+
+            let (lx, ly) = if self.mousestick_usage {
+                (0., 0.)
+            } else {
+                (axis(pad.lx), axis(pad.ly))
+            };
+            add_stick_event(io, Key::GamepadLStickLeft, -lx);
+            add_stick_event(io, Key::GamepadLStickRight, lx);
+            add_stick_event(io, Key::GamepadLStickUp, -ly);
+            add_stick_event(io, Key::GamepadLStickDown, ly);
+            add_stick_event(io, Key::GamepadRStickLeft, -axis(pad.rx));
+            add_stick_event(io, Key::GamepadRStickRight, axis(pad.rx));
+            add_stick_event(io, Key::GamepadRStickUp, -axis(pad.ry));
+            add_stick_event(io, Key::GamepadRStickDown, axis(pad.ry));
+        }
+
+        self.update_mousestick(io, pad.as_ref());
+
+        io.mouse_down[0] = self.mouse_pressed[0] != 0;
+        io.mouse_down[1] = self.mouse_pressed[1] != 0;
+        io.mouse_down[2] = self.mouse_pressed[2] != 0;
+
+        self.mx = self.mx.clamp(0, self.display_size[0] as i32);
+        self.my = self.my.clamp(0, self.display_size[1] as i32);
+
+        io.mouse_pos = [self.mx as f32, self.my as f32];
+    }
+
+    /// The first front finger moves the pointer and holds the left button.
+    fn update_touch(&mut self, points: &[TouchPoint]) {
+        match points
+            .first()
+            .and_then(|point| self.touch_to_display([point.x, point.y]))
+        {
+            Some([x, y]) => {
+                self.mx = x as i32;
+                self.my = y as i32;
+                self.mouse_pressed[0] = 1;
+            }
+            None => self.mouse_pressed[0] = 0,
+        }
+    }
+
+    fn update_gestures(&mut self, io: &mut Io, points: &[TouchPoint]) {
+        let gestures = match &mut self.gestures {
+            Some(recognizer) => recognizer.update(points, io.delta_time),
+            None => return,
+        };
+
+        if let Some([x, y]) = gestures.pointer.and_then(|p| self.touch_to_display(p)) {
+            self.mx = x as i32;
+            self.my = y as i32;
+        }
+        self.mouse_pressed[0] = gestures.left_down as i32;
+        self.mouse_pressed[1] = gestures.right_down as i32;
+        if gestures.wheel != [0., 0.] {
+            io.add_mouse_wheel_event(gestures.wheel);
+        }
+        self.last_gestures = gestures;
+    }
+
+    fn touch_to_display(&self, position: [f32; 2]) -> Option<[f32; 2]> {
+        touch_to_display(position, self.viewport, self.display_size)
+    }
+
+    /// Drops the fingers outside the viewport, e.g. on letterbox bars.
+    fn viewport_touches(&self, touch: TouchState) -> TouchState {
+        let mut inside = TouchState::default();
+        for point in touch.points() {
+            if self.touch_to_display([point.x, point.y]).is_some() {
+                inside.points[inside.count] = *point;
+                inside.count += 1;
+            }
+        }
+        inside
+    }
+
+    fn update_rear_touch(&mut self, io: &mut Io, points: &[TouchPoint]) {
+        if points.is_empty() {
+            // Release the button the second rear finger held.
+            if self.rear_touch == RearTouch::Pointer
+                && matches!(self.last_rear, Some((count, _)) if count >= 2)
+            {
+                self.mouse_pressed[0] = 0;
+            }
+            self.last_rear = None;
+            return;
+        }
+
+        match self.rear_touch {
+            RearTouch::Off => {}
+            RearTouch::Scroll => {
+                let count = points.len() as f32;
+                let centroid = points.iter().fold([0., 0.], |sum, p| {
+                    [sum[0] + p.x / count, sum[1] + p.y / count]
+                });
+                if let Some((last_count, last)) = self.last_rear {
+                    // A finger landing or lifting moves the centroid without a drag.
+                    if last_count == points.len() {
+                        let speed = self.rear_scroll_speed;
+                        let horizontal = if points.len() >= 2 {
+                            (centroid[0] - last[0]) * speed
+                        } else {
+                            0.
+                        };
+                        let vertical = (centroid[1] - last[1]) * speed;
+                        if horizontal != 0. || vertical != 0. {
+                            io.add_mouse_wheel_event([horizontal, vertical]);
+                        }
+                    }
+                }
+                self.last_rear = Some((points.len(), centroid));
+            }
+            RearTouch::Pointer => {
+                // The front panel wins while it is pressed.
+                let rear_pressed = matches!(self.last_rear, Some((count, _)) if count >= 2);
+                if self.mouse_pressed[0] == 0 || rear_pressed {
+                    // Only fingers inside the viewport get here.
+                    if let Some([x, y]) = self.touch_to_display([points[0].x, points[0].y]) {
+                        self.mx = x as i32;
+                        self.my = y as i32;
+                    }
+                    self.mouse_pressed[0] = (points.len() >= 2) as i32;
+                }
+                self.last_rear = Some((points.len(), [points[0].x, points[0].y]));
+            }
+        }
+    }
+
+    fn update_mousestick(&mut self, io: &Io, pad: Option<&PadState>) {
+        let pad = match pad {
+            Some(pad) if self.mousestick_usage => pad,
+            _ => {
+                // Release the buttons the triggers held when the stick mouse turns off.
+                self.update_mousestick_buttons(0);
+                return;
+            }
+        };
+        self.update_mousestick_buttons(pad.buttons & (SCE_CTRL_LTRIGGER | SCE_CTRL_RTRIGGER));
+
+        let mut lx = (axis(pad.lx) * 32768.) as i32;
+        let mut ly = (axis(pad.ly) * 32768.) as i32;
+        rescale_analog(&mut lx, &mut ly, 7680);
+        let (x, y) = (lx as f32 / 32768., ly as f32 / 32768.);
+        let magnitude = x.hypot(y);
+        if magnitude > 0. {
+            let speed = self.mousestick_speed
+                * magnitude.powf(self.mousestick_acceleration - 1.)
+                * io.delta_time;
+            self.hires_x += x * speed;
+            self.hires_y += y * speed;
+            // Move by whole pixels and keep the rest for the next frame.
+            let (dx, dy) = (self.hires_x.trunc(), self.hires_y.trunc());
+            self.mx += dx as i32;
+            self.my += dy as i32;
+            self.hires_x -= dx;
+            self.hires_y -= dy;
+        }
+    }
+
+    /// Holds the left and right buttons while their trigger is down and releases them when it
+    /// lifts, leaving them to touch input otherwise. Touch input rewrites the buttons every
+    /// frame, so held triggers are applied after it.
+    fn update_mousestick_buttons(&mut self, buttons: u32) {
+        let released = self.mousestick_buttons & !buttons;
+        for (button, mask) in [(0, SCE_CTRL_LTRIGGER), (1, SCE_CTRL_RTRIGGER)] {
+            if buttons & mask != 0 {
+                self.mouse_pressed[button] = 1;
+            } else if released & mask != 0 {
+                self.mouse_pressed[button] = 0;
+            }
+        }
+        self.mousestick_buttons = buttons;
+    }
+}
+
+impl<I: InputBackend, C: Clock> Drop for VitaPlatform<I, C> {
+    fn drop(&mut self) {
+        if let Some(io) = self.registration.io_mut() {
+            io.BackendPlatformName = std::ptr::null();
+            io.BackendFlags &=
+                !((BackendFlags::HAS_GAMEPAD | BackendFlags::HAS_MOUSE_CURSORS).bits() as i32);
+            // Nothing drives the cursor once the platform is gone.
+            if self.draw_cursor {
+                io.MouseDrawCursor = false;
+            }
+        }
+    }
+}
+
+/// Maps a normalized touch position to display coordinates, through the screen rectangle
+/// `viewport` the display is drawn into, or `None` if the position is outside the viewport.
+pub fn touch_to_display(
+    position: [f32; 2],
+    viewport: [f32; 4],
+    display_size: [f32; 2],
+) -> Option<[f32; 2]> {
+    let x = (position[0] * SCREEN_SIZE[0] - viewport[0]) / viewport[2];
+    let y = (position[1] * SCREEN_SIZE[1] - viewport[1]) / viewport[3];
+    if !(0. ..=1.).contains(&x) || !(0. ..=1.).contains(&y) {
+        return None;
+    }
+    Some([x * display_size[0], y * display_size[1]])
+}
+
+/// A raw stick reading as a deflection from -1 to 1.
+fn axis(value: u8) -> f32 {
+    ((value as f32 - STICK_CENTER as f32) / 127.).clamp(-1., 1.)
+}
+
+/// Reports one direction of a stick axis, `value` being the signed deflection towards it.
+fn add_stick_event(io: &mut Io, key: Key, value: f32) {
+    let value = ((value - STICK_DEAD_ZONE) / (1. - STICK_DEAD_ZONE)).clamp(0., 1.);
+    io.add_key_analog_event(key, value > 0.1, value);
+}
+
+fn rescale_analog(x: &mut i32, y: &mut i32, dead: i32) {
+    let analog_x = *x as f32;
+    let analog_y = *y as f32;
+    let dead_zone = dead as f32;
+    let maximum = 32768.;
+    let magnitude = (analog_x * analog_x + analog_y * analog_y).sqrt();
+    if magnitude >= dead_zone {
+        let scaling_factor = maximum / magnitude * (magnitude - dead_zone) / (maximum - dead_zone);
+        *x = (analog_x * scaling_factor) as i32;
+        *y = (analog_y * scaling_factor) as i32;
+    } else {
+        *x = 0;
+        *y = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_time_limits() {
+        let builder = VitaPlatformBuilder::new();
+        assert!(builder.clone().max_delta_time(1e-7).validate().is_err());
+        assert!(builder.clone().max_delta_time(f32::NAN).validate().is_err());
+        assert!(builder
+            .clone()
+            .max_delta_time(0.5)
+            .suspend_threshold(0.25)
+            .validate()
+            .is_err());
+        assert!(builder
+            .max_delta_time(1e-6)
+            .suspend_threshold(1e-6)
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn touch_needs_its_panel() {
+        let back = VitaPlatformBuilder::new().touch_ports(TouchPorts::Back);
+        assert_eq!(back.clone().validate(), Err(ConfigError::NoTouchPorts));
+        assert!(back
+            .touch_usage(false)
+            .rear_touch(RearTouch::Scroll)
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn full_screen_mapping() {
+        let viewport = [0., 0., 960., 544.];
+        assert_eq!(
+            touch_to_display([0., 0.], viewport, [960., 544.]),
+            Some([0., 0.])
+        );
+        assert_eq!(
+            touch_to_display([0.5, 0.25], viewport, [960., 544.]),
+            Some([480., 136.])
+        );
+        assert_eq!(
+            touch_to_display([1., 1.], viewport, [960., 544.]),
+            Some([960., 544.])
+        );
+    }
+
+    #[test]
+    fn scaled_display_mapping() {
+        let viewport = [0., 0., 960., 544.];
+        assert_eq!(
+            touch_to_display([0.5, 0.5], viewport, [720., 408.]),
+            Some([360., 204.])
+        );
+        assert_eq!(
+            touch_to_display([1., 1.], viewport, [720., 408.]),
+            Some([720., 408.])
+        );
+    }
+
+    #[test]
+    fn offset_viewport_mapping() {
+        // A 720x408 display letterboxed in the middle of the screen.
+        let viewport = [120., 68., 720., 408.];
+        assert_eq!(
+            touch_to_display([0.125, 0.125], viewport, [720., 408.]),
+            Some([0., 0.])
+        );
+        assert_eq!(
+            touch_to_display([0.5, 0.5], viewport, [720., 408.]),
+            Some([360., 204.])
+        );
+        assert_eq!(
+            touch_to_display([0.875, 0.875], viewport, [720., 408.]),
+            Some([720., 408.])
+        );
+        // A half size sub-viewport showing a full size display.
+        assert_eq!(
+            touch_to_display([0.75, 0.75], [480., 272., 480., 272.], [960., 544.]),
+            Some([480., 272.])
+        );
+    }
+
+    #[test]
+    fn points_outside_the_viewport() {
+        let viewport = [120., 68., 720., 408.];
+        for position in [[0.05, 0.5], [0.95, 0.5], [0.5, 0.05], [0.5, 0.95], [0., 0.]] {
+            assert_eq!(touch_to_display(position, viewport, [720., 408.]), None);
+        }
+    }
+
+    #[test]
+    fn mousestick_limits() {
+        let builder = VitaPlatformBuilder::new();
+        for speed in [f32::NAN, f32::INFINITY, -1.] {
+            assert!(matches!(
+                builder.clone().mousestick_speed(speed).validate(),
+                Err(ConfigError::MousestickSpeed(_))
+            ));
+        }
+        for acceleration in [f32::NAN, 0.5, 0.] {
+            assert!(matches!(
+                builder
+                    .clone()
+                    .mousestick_acceleration(acceleration)
+                    .validate(),
+                Err(ConfigError::MousestickAcceleration(_))
+            ));
+        }
+        assert!(builder
+            .mousestick_speed(0.)
+            .mousestick_acceleration(1.)
+            .validate()
+            .is_ok());
+    }
+}
@@ -0,0 +1,33 @@
+use imgui::sys::{igGetCurrentContext, igGetIO, ImGuiContext, ImGuiIO};
+
+use crate::builder::ConfigError;
+
+pub(crate) const RENDERER_NAME: &str = "imgui-rs-vitagl-renderer";
+pub(crate) const PLATFORM_NAME: &str = "imgui-rs-vita-platform";
+
+/// The context a renderer or platform registered with, so `Drop` can clear its name and flags.
+pub(crate) struct Registration(*mut ImGuiContext);
+
+impl Registration {
+    /// Fails if `registered` already names a backend of the same kind.
+    pub(crate) fn check(registered: Option<&str>) -> Result<(), ConfigError> {
+        match registered {
+            Some(name) => Err(ConfigError::AlreadyInitialized(name.to_owned())),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn new(_ctx: &mut imgui::Context) -> Self {
+        Self(unsafe { igGetCurrentContext() })
+    }
+
+    /// The registered context's IO, unless it was destroyed or is no longer current.
+    pub(crate) fn io_mut(&mut self) -> Option<&mut ImGuiIO> {
+        unsafe {
+            if self.0.is_null() || igGetCurrentContext() != self.0 {
+                return None;
+            }
+            igGetIO().as_mut()
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::buffer::MemBuffer;
+use crate::error::RendererError;
+
+/// What `render` does when a frame needs more vertices than its share of the vertex pools.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Fails the draw with `RendererError::BufferOverflow`.
+    #[default]
+    Fail,
+    /// Allocates pools at least twice as large. The old pools are freed once no frame in
+    /// flight reads them anymore.
+    Grow,
+}
+
+/// When the vertex ring moves on to the next region.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrameAdvance {
+    /// Every `render` call takes the next region. Fits apps that render once per swap.
+    #[default]
+    Render,
+    /// Only `ImguiRenderer::end_frame` takes the next region, so the `render` calls before a
+    /// swap share one. For apps that render several times per swap, e.g. a UI pass and an
+    /// overlay.
+    Manual,
+}
+
+/// Mapped position, texcoord and color arrays of `len` vertices each.
+struct Pools {
+    vertex: MemBuffer<f32>,
+    texcoord: MemBuffer<f32>,
+    color: MemBuffer<u8>,
+    len: usize,
+}
+
+impl Pools {
+    fn alloc(len: usize) -> Result<Self, RendererError> {
+        let components = |n: usize| len.checked_mul(n).ok_or(RendererError::Alloc(usize::MAX));
+        Ok(Self {
+            vertex: MemBuffer::alloc(components(3)?)?,
+            texcoord: MemBuffer::alloc(components(2)?)?,
+            color: MemBuffer::alloc(components(4)?)?,
+            len,
+        })
+    }
+}
+
+/// Vertex slots handed out by `VertexRing::alloc`.
+pub(crate) struct VertexSlots {
+    pub(crate) vertex: *mut f32,
+    pub(crate) texcoord: *mut f32,
+    pub(crate) color: *mut u8,
+}
+
+/// The vertex pools of de-indexed draws, split into one region per frame in flight. The GPU
+/// reads mapped memory when it executes a frame, so a region is only reused `frames` frames
+/// later, once vitaGL is done with it. There are no fences: a frame is whatever the caller
+/// advances with `begin_frame`.
+pub(crate) struct VertexRing {
+    pools: Pools,
+    frames: usize,
+    policy: OverflowPolicy,
+    frame: u64,
+    offset: usize,
+    /// Pools replaced by `Grow`, with the frame that last used them.
+    retired: Vec<(Pools, u64)>,
+}
+
+impl VertexRing {
+    pub(crate) fn new(
+        len: usize,
+        frames: usize,
+        policy: OverflowPolicy,
+    ) -> Result<Self, RendererError> {
+        Ok(Self {
+            pools: Pools::alloc(len)?,
+            frames,
+            policy,
+            frame: 0,
+            offset: 0,
+            retired: Vec::new(),
+        })
+    }
+
+    /// Vertices each frame may use.
+    pub(crate) fn capacity(&self) -> usize {
+        self.pools.len / self.frames
+    }
+
+    /// Moves to the next frame's region and frees retired pools no frame in flight reads.
+    pub(crate) fn begin_frame(&mut self) {
+        self.frame += 1;
+        self.offset = 0;
+
+        let (frame, frames) = (self.frame, self.frames as u64);
+        self.retired
+            .retain(|(_, last_used)| last_used + frames > frame);
+    }
+
+    pub(crate) fn alloc(&mut self, count: usize) -> Result<VertexSlots, RendererError> {
+        if self.offset + count > self.capacity() {
+            let overflow = RendererError::BufferOverflow {
+                needed: self.offset + count,
+                capacity: self.capacity(),
+            };
+            match self.policy {
+                OverflowPolicy::Fail => return Err(overflow),
+                OverflowPolicy::Grow => {
+                    let len = self
+                        .pools
+                        .len
+                        .checked_mul(2)
+                        .zip(count.checked_mul(self.frames))
+                        .map(|(doubled, needed)| doubled.max(needed))
+                        .ok_or(overflow)?;
+                    let pools = std::mem::replace(&mut self.pools, Pools::alloc(len)?);
+                    self.retired.push((pools, self.frame));
+                    self.offset = 0;
+                }
+            }
+        }
+
+        let start = self.capacity() * (self.frame as usize % self.frames) + self.offset;
+        self.offset += count;
+        unsafe {
+            Ok(VertexSlots {
+                vertex: self.pools.vertex.as_mut_ptr().add(start * 3),
+                texcoord: self.pools.texcoord.as_mut_ptr().add(start * 2),
+                color: self.pools.color.as_mut_ptr().add(start * 4),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_pools_fail_to_allocate() {
+        let ring = VertexRing::new(usize::MAX / 2, 1, OverflowPolicy::Fail);
+        assert_eq!(ring.err(), Some(RendererError::Alloc(usize::MAX)));
+    }
+
+    /// The vertex index of `slots` in the ring's current pools.
+    fn start(ring: &VertexRing, slots: &VertexSlots) -> usize {
+        let start = unsafe { slots.vertex.offset_from(ring.pools.vertex.as_ptr()) } as usize / 3;
+        assert_eq!(
+            unsafe { slots.color.offset_from(ring.pools.color.as_ptr()) },
+            start as isize * 4
+        );
+        start
+    }
+
+    #[test]
+    fn frames_in_flight_get_disjoint_regions() {
+        let mut ring = VertexRing::new(30, 3, OverflowPolicy::Fail).unwrap();
+        let mut starts = Vec::new();
+        for _ in 0..7 {
+            ring.begin_frame();
+            let first = ring.alloc(4).unwrap();
+            let second = ring.alloc(6).unwrap();
+            assert_eq!(start(&ring, &second), start(&ring, &first) + 4);
+            starts.push(start(&ring, &first));
+        }
+
+        // Each region is 10 vertices, reused three frames later.
+        assert_eq!(starts, [10, 20, 0, 10, 20, 0, 10]);
+    }
+
+    #[test]
+    fn fail_policy_reports_overflow() {
+        let mut ring = VertexRing::new(30, 3, OverflowPolicy::Fail).unwrap();
+        ring.begin_frame();
+        ring.alloc(6).unwrap();
+        assert_eq!(
+            ring.alloc(5).err(),
+            Some(RendererError::BufferOverflow {
+                needed: 11,
+                capacity: 10,
+            })
+        );
+
+        ring.begin_frame();
+        assert!(ring.alloc(10).is_ok());
+    }
+
+    #[test]
+    fn grow_keeps_retired_pools_for_frames_in_flight() {
+        let mut ring = VertexRing::new(30, 3, OverflowPolicy::Grow).unwrap();
+        ring.begin_frame();
+        ring.alloc(10).unwrap();
+        let slots = ring.alloc(25).unwrap();
+        assert_eq!(ring.pools.len, 75);
+        assert_eq!(ring.capacity(), 25);
+        assert_eq!(start(&ring, &slots), 25);
+        assert_eq!(ring.retired.len(), 1);
+
+        // The old pools were last used by frame 1, which is in flight until frame 4.
+        ring.begin_frame();
+        ring.begin_frame();
+        assert_eq!(ring.retired.len(), 1);
+        ring.begin_frame();
+        assert!(ring.retired.is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+use crate::backend::GlBackend;
+use crate::error::RendererError;
+use crate::gl::*;
+
+pub const ATTRIB_POSITION: GLuint = 0;
+pub const ATTRIB_TEXCOORD: GLuint = 1;
+pub const ATTRIB_COLOR: GLuint = 2;
+
+const DEFAULT_VERTEX_SHADER: &str = r#"
+void main(
+    float3 position,
+    float2 texcoord,
+    float4 color,
+    uniform float4x4 ProjMtx,
+    float2 out vTexcoord : TEXCOORD0,
+    float4 out vColor : COLOR,
+    float4 out gl_Position : POSITION)
+{
+    vTexcoord = texcoord;
+    vColor = color;
+    gl_Position = mul(float4(position.xy, 0.0, 1.0), ProjMtx);
+}
+"#;
+
+const DEFAULT_FRAGMENT_SHADER: &str = r#"
+float4 main(
+    float2 vTexcoord : TEXCOORD0,
+    float4 vColor : COLOR,
+    uniform sampler2D Texture) : COLOR
+{
+    return vColor * tex2D(Texture, vTexcoord);
+}
+"#;
+
+/// Sources for the ImGui program, compiled at runtime by vitaGL. Custom shaders must use the
+/// attribute names `position`, `texcoord` and `color` and the uniforms `ProjMtx` and `Texture`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderSource {
+    pub vertex: String,
+    pub fragment: String,
+}
+
+impl Default for ShaderSource {
+    fn default() -> Self {
+        Self {
+            vertex: DEFAULT_VERTEX_SHADER.to_string(),
+            fragment: DEFAULT_FRAGMENT_SHADER.to_string(),
+        }
+    }
+}
+
+/// Whether draws go through the fixed-function pipeline or through the renderer's own program.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Pipeline {
+    #[default]
+    FixedFunction,
+    Shaders(ShaderSource),
+}
+
+pub(crate) struct ShaderProgram {
+    pub program: GLuint,
+    vertex: GLuint,
+    fragment: GLuint,
+    pub proj_mtx: GLint,
+    pub texture: GLint,
+}
+
+impl ShaderProgram {
+    pub fn new<G: GlBackend>(gl: &mut G, source: &ShaderSource) -> Result<Self, RendererError> {
+        let vertex = compile(gl, GL_VERTEX_SHADER, &source.vertex)?;
+        let fragment = match compile(gl, GL_FRAGMENT_SHADER, &source.fragment) {
+            Ok(fragment) => fragment,
+            Err(error) => {
+                gl.delete_shader(vertex);
+                return Err(error);
+            }
+        };
+
+        let program = gl.create_program();
+        gl.attach_shader(program, vertex);
+        gl.attach_shader(program, fragment);
+        gl.bind_attrib_location(program, ATTRIB_POSITION, "position", 3, GL_FLOAT);
+        gl.bind_attrib_location(program, ATTRIB_TEXCOORD, "texcoord", 2, GL_FLOAT);
+        gl.bind_attrib_location(program, ATTRIB_COLOR, "color", 4, GL_UNSIGNED_BYTE);
+        gl.link_program(program);
+        if gl.get_programiv(program, GL_LINK_STATUS) == GL_FALSE as GLint {
+            let log = gl.get_program_info_log(program);
+            gl.delete_program(program);
+            gl.delete_shader(vertex);
+            gl.delete_shader(fragment);
+            return Err(RendererError::Shader(log));
+        }
+
+        let proj_mtx = gl.get_uniform_location(program, "ProjMtx");
+        let texture = gl.get_uniform_location(program, "Texture");
+
+        Ok(Self {
+            program,
+            vertex,
+            fragment,
+            proj_mtx,
+            texture,
+        })
+    }
+
+    pub fn delete<G: GlBackend>(&self, gl: &mut G) {
+        gl.delete_program(self.program);
+        gl.delete_shader(self.vertex);
+        gl.delete_shader(self.fragment);
+    }
+}
+
+fn compile<G: GlBackend>(gl: &mut G, ty: GLenum, source: &str) -> Result<GLuint, RendererError> {
+    let shader = gl.create_shader(ty);
+    gl.shader_source(shader, source);
+    gl.compile_shader(shader);
+    if gl.get_shaderiv(shader, GL_COMPILE_STATUS) == GL_FALSE as GLint {
+        let log = gl.get_shader_info_log(shader);
+        gl.delete_shader(shader);
+        return Err(RendererError::Shader(log));
+    }
+    Ok(shader)
+}
+
+/// Column-major orthographic projection for the ImGui display rectangle.
+pub(crate) fn ortho_projection(pos: [f32; 2], size: [f32; 2]) -> [f32; 16] {
+    let (l, t) = (pos[0], pos[1]);
+    let (r, b) = (pos[0] + size[0], pos[1] + size[1]);
+    #[rustfmt::skip]
+    let projection = [
+        2. / (r - l), 0., 0., 0.,
+        0., 2. / (t - b), 0., 0.,
+        0., 0., -1., 0.,
+        (r + l) / (l - r), (t + b) / (b - t), 0., 1.,
+    ];
+    projection
+}
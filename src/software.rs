@@ -0,0 +1,1098 @@
+//! A pure-Rust `GlBackend` that records every call into a command log and rasterizes the draws
+//! into an RGBA framebuffer, so frames can be checked against golden images without a GPU.
+//!
+//! Shaders are not interpreted: any bound program is assumed to be the renderer's ImGui program,
+//! reading position, texcoord and color from attributes 0, 1 and 2 and transforming with its
+//! `ProjMtx` uniform.
+
+use std::collections::HashMap;
+use std::ffi::c_void;
+
+use crate::backend::GlBackend;
+use crate::gl::*;
+
+/// One vertex as it was fed to a draw call, after resolving the index buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vertex {
+    pub pos: [f32; 3],
+    pub uv: [f32; 2],
+    pub col: [u8; 4],
+}
+
+/// A recorded GL call. Pointer arguments are not recorded, only what was read through them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GlCommand {
+    GenTexture(GLuint),
+    DeleteTexture(GLuint),
+    BindTexture {
+        target: GLenum,
+        texture: GLuint,
+    },
+    TexParameteri {
+        target: GLenum,
+        pname: GLenum,
+        param: GLint,
+    },
+    GenerateMipmap(GLenum),
+    TexImage2D {
+        texture: GLuint,
+        level: GLint,
+        internal_format: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+    },
+    UseProgram(GLuint),
+    Enable(GLenum),
+    Disable(GLenum),
+    BlendFunc {
+        sfactor: GLenum,
+        dfactor: GLenum,
+    },
+    EnableClientState(GLenum),
+    DisableClientState(GLenum),
+    PolygonMode {
+        face: GLenum,
+        mode: GLenum,
+    },
+    Viewport([GLint; 4]),
+    Scissor([GLint; 4]),
+    MatrixMode(GLenum),
+    PushMatrix,
+    PopMatrix,
+    LoadIdentity,
+    Ortho([f64; 6]),
+    IndexPointerMapped,
+    VertexPointerMapped {
+        size: GLint,
+    },
+    TexCoordPointerMapped,
+    ColorPointerMapped {
+        ty: GLenum,
+    },
+    VertexAttribPointerMapped {
+        index: GLuint,
+    },
+    DrawObjects {
+        mode: GLenum,
+        texture: GLuint,
+        scissor: Option<[GLint; 4]>,
+        vertices: Vec<Vertex>,
+    },
+    VertexPointer {
+        size: GLint,
+        ty: GLenum,
+        stride: GLsizei,
+    },
+    TexCoordPointer {
+        size: GLint,
+        ty: GLenum,
+        stride: GLsizei,
+    },
+    ColorPointer {
+        size: GLint,
+        ty: GLenum,
+        stride: GLsizei,
+    },
+    DrawElements {
+        mode: GLenum,
+        ty: GLenum,
+        texture: GLuint,
+        scissor: Option<[GLint; 4]>,
+        vertices: Vec<Vertex>,
+    },
+    CreateShader {
+        shader: GLuint,
+        ty: GLenum,
+    },
+    ShaderSource {
+        shader: GLuint,
+        source: String,
+    },
+    CompileShader(GLuint),
+    DeleteShader(GLuint),
+    CreateProgram(GLuint),
+    AttachShader {
+        program: GLuint,
+        shader: GLuint,
+    },
+    BindAttribLocation {
+        program: GLuint,
+        index: GLuint,
+        name: String,
+        size: GLint,
+        ty: GLenum,
+    },
+    LinkProgram(GLuint),
+    DeleteProgram(GLuint),
+    UniformMatrix4fv {
+        location: GLint,
+        value: [f32; 16],
+    },
+    Uniform1i {
+        location: GLint,
+        value: GLint,
+    },
+    EnableVertexAttribArray(GLuint),
+    DisableVertexAttribArray(GLuint),
+    VertexAttribPointer {
+        index: GLuint,
+        size: GLint,
+        ty: GLenum,
+        normalized: bool,
+        stride: GLsizei,
+    },
+}
+
+#[derive(Default)]
+struct Program {
+    uniforms: Vec<String>,
+    attrib_formats: HashMap<GLuint, (GLint, GLenum)>,
+    matrices: HashMap<GLint, Matrix>,
+}
+
+#[derive(Default)]
+struct Texture {
+    width: usize,
+    height: usize,
+    rgba: Vec<u8>,
+    filter: GLenum,
+}
+
+type Matrix = [f32; 16];
+
+const IDENTITY: Matrix = [
+    1., 0., 0., 0., //
+    0., 1., 0., 0., //
+    0., 0., 1., 0., //
+    0., 0., 0., 1.,
+];
+
+fn mul(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut out = [0.; 16];
+    for col in 0..4 {
+        for row in 0..4 {
+            out[col * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[col * 4 + k]).sum();
+        }
+    }
+    out
+}
+
+fn transform(m: &Matrix, v: [f32; 4]) -> [f32; 4] {
+    let mut out = [0.; 4];
+    for (row, o) in out.iter_mut().enumerate() {
+        *o = (0..4).map(|k| m[k * 4 + row] * v[k]).sum();
+    }
+    out
+}
+
+/// `GlBackend` that records and rasterizes into memory. The framebuffer is RGBA8 with the
+/// first row at the top, ready to be written out or compared as an image.
+pub struct SoftwareGl {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    commands: Vec<GlCommand>,
+
+    textures: HashMap<GLuint, Texture>,
+    next_texture: GLuint,
+    bound_texture: GLuint,
+    enabled: Vec<GLenum>,
+    blend_func: (GLenum, GLenum),
+    polygon_mode: [GLenum; 2],
+    viewport: [GLint; 4],
+    scissor: [GLint; 4],
+
+    matrix_mode: GLenum,
+    projection: Vec<Matrix>,
+    modelview: Vec<Matrix>,
+
+    index_pointer: *const u16,
+    vertex_pointer: ArrayPointer,
+    tex_coord_pointer: ArrayPointer,
+    color_pointer: ArrayPointer,
+
+    next_object: GLuint,
+    programs: HashMap<GLuint, Program>,
+    current_program: GLuint,
+    attribs: HashMap<GLuint, ArrayPointer>,
+
+    error: GLenum,
+}
+
+/// A client array as described by `gl*Pointer`; the mapped variants are tightly packed.
+#[derive(Clone, Copy)]
+struct ArrayPointer {
+    size: GLint,
+    ty: GLenum,
+    stride: GLsizei,
+    pointer: *const u8,
+}
+
+impl ArrayPointer {
+    const NULL: Self = Self {
+        size: 0,
+        ty: GL_FLOAT,
+        stride: 0,
+        pointer: std::ptr::null(),
+    };
+
+    fn packed(size: GLint, ty: GLenum, pointer: *const c_void) -> Self {
+        Self::strided(size, ty, 0, pointer)
+    }
+
+    fn strided(size: GLint, ty: GLenum, stride: GLsizei, pointer: *const c_void) -> Self {
+        let component = if ty == GL_UNSIGNED_BYTE { 1 } else { 4 };
+        Self {
+            size,
+            ty,
+            stride: if stride == 0 {
+                size * component
+            } else {
+                stride
+            },
+            pointer: pointer as *const u8,
+        }
+    }
+
+    unsafe fn element(&self, index: usize) -> *const u8 {
+        self.pointer.add(index * self.stride as usize)
+    }
+}
+
+impl SoftwareGl {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height * 4],
+            commands: Vec::new(),
+            textures: HashMap::new(),
+            next_texture: 1,
+            bound_texture: 0,
+            enabled: Vec::new(),
+            blend_func: (GL_ONE, GL_ZERO),
+            polygon_mode: [GL_FILL, GL_FILL],
+            viewport: [0, 0, width as GLint, height as GLint],
+            scissor: [0, 0, width as GLint, height as GLint],
+            matrix_mode: GL_MODELVIEW,
+            projection: vec![IDENTITY],
+            modelview: vec![IDENTITY],
+            index_pointer: std::ptr::null(),
+            vertex_pointer: ArrayPointer::NULL,
+            tex_coord_pointer: ArrayPointer::NULL,
+            color_pointer: ArrayPointer::NULL,
+            next_object: 1,
+            programs: HashMap::new(),
+            current_program: 0,
+            attribs: HashMap::new(),
+            error: GL_NO_ERROR,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * self.width + x) * 4;
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+
+    pub fn clear(&mut self, color: [u8; 4]) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&color);
+        }
+    }
+
+    pub fn commands(&self) -> &[GlCommand] {
+        &self.commands
+    }
+
+    pub fn take_commands(&mut self) -> Vec<GlCommand> {
+        std::mem::take(&mut self.commands)
+    }
+
+    /// Counts the pixels whose channels differ from `expected` by more than `tolerance`.
+    /// `expected` is RGBA8 in the framebuffer's layout, e.g. a decoded golden PNG.
+    pub fn mismatched_pixels(&self, expected: &[u8], tolerance: u8) -> usize {
+        assert_eq!(expected.len(), self.pixels.len(), "image size mismatch");
+        self.pixels
+            .chunks_exact(4)
+            .zip(expected.chunks_exact(4))
+            .filter(|(a, b)| {
+                a.iter()
+                    .zip(b.iter())
+                    .any(|(a, b)| a.abs_diff(*b) > tolerance)
+            })
+            .count()
+    }
+
+    /// Records `error` for `get_error` unless an earlier one is still pending, as GL does.
+    fn set_error(&mut self, error: GLenum) {
+        if self.error == GL_NO_ERROR {
+            self.error = error;
+        }
+    }
+
+    fn cap_enabled(&self, cap: GLenum) -> bool {
+        self.enabled.contains(&cap)
+    }
+
+    fn current_matrix(&mut self) -> &mut Matrix {
+        let stack = if self.matrix_mode == GL_PROJECTION {
+            &mut self.projection
+        } else {
+            &mut self.modelview
+        };
+        stack.last_mut().unwrap()
+    }
+
+    /// The position, texcoord and color arrays feeding the next draw.
+    fn arrays(&self) -> [ArrayPointer; 3] {
+        if self.current_program == 0 {
+            return [
+                self.vertex_pointer,
+                self.tex_coord_pointer,
+                self.color_pointer,
+            ];
+        }
+        [0, 1, 2].map(|i| self.attribs.get(&i).copied().unwrap_or(ArrayPointer::NULL))
+    }
+
+    fn mvp(&self) -> Matrix {
+        if self.current_program == 0 {
+            return mul(
+                self.projection.last().unwrap(),
+                self.modelview.last().unwrap(),
+            );
+        }
+        self.programs
+            .get(&self.current_program)
+            .and_then(|program| {
+                let location = program.uniforms.iter().position(|u| u == "ProjMtx")?;
+                program.matrices.get(&(location as GLint)).copied()
+            })
+            .unwrap_or(IDENTITY)
+    }
+
+    unsafe fn fetch_vertex(arrays: &[ArrayPointer; 3], index: usize) -> Vertex {
+        let [position, texcoord, color] = arrays;
+
+        let mut pos = [0.; 3];
+        let p = position.element(index) as *const f32;
+        for (i, c) in pos.iter_mut().enumerate().take(position.size as usize) {
+            *c = p.add(i).read_unaligned();
+        }
+
+        let mut uv = [0.; 2];
+        if !texcoord.pointer.is_null() {
+            let texcoord = texcoord.element(index) as *const f32;
+            uv = [texcoord.read_unaligned(), texcoord.add(1).read_unaligned()];
+        }
+
+        let mut col = [255; 4];
+        if !color.pointer.is_null() {
+            let ty = color.ty;
+            let color = color.element(index);
+            if ty == GL_FLOAT {
+                let color = color as *const f32;
+                col = [0, 1, 2, 3].map(|i| (color.add(i).read_unaligned() * 255.) as u8);
+            } else {
+                col = [*color, *color.add(1), *color.add(2), *color.add(3)];
+            }
+        }
+
+        Vertex { pos, uv, col }
+    }
+
+    /// Fetches and rasterizes the vertices at `indices`, returning the scissor box in effect and
+    /// the vertices for the command log.
+    ///
+    /// # Safety
+    ///
+    /// The bound vertex arrays must be readable at every index in `indices`.
+    unsafe fn draw(
+        &mut self,
+        mode: GLenum,
+        indices: &[usize],
+    ) -> (Option<[GLint; 4]>, Vec<Vertex>) {
+        let arrays = self.arrays();
+        let vertices: Vec<Vertex> = if arrays[0].pointer.is_null() {
+            Vec::new()
+        } else {
+            indices
+                .iter()
+                .map(|index| Self::fetch_vertex(&arrays, *index))
+                .collect()
+        };
+
+        let scissor = self.cap_enabled(GL_SCISSOR_TEST).then_some(self.scissor);
+        if mode == GL_TRIANGLES {
+            let mvp = self.mvp();
+            for triangle in vertices.chunks_exact(3) {
+                self.rasterize_triangle(&mvp, [&triangle[0], &triangle[1], &triangle[2]], scissor);
+            }
+        }
+
+        (scissor, vertices)
+    }
+
+    fn sample(texture: &Texture, u: f32, v: f32, filter: GLenum) -> [f32; 4] {
+        let texel = |x: i64, y: i64| {
+            let x = x.rem_euclid(texture.width as i64) as usize;
+            let y = y.rem_euclid(texture.height as i64) as usize;
+            let i = (y * texture.width + x) * 4;
+            let t = &texture.rgba[i..i + 4];
+            [t[0] as f32, t[1] as f32, t[2] as f32, t[3] as f32]
+        };
+
+        let x = u * texture.width as f32 - 0.5;
+        let y = v * texture.height as f32 - 0.5;
+        if filter == GL_NEAREST {
+            return texel((x + 0.5).floor() as i64, (y + 0.5).floor() as i64);
+        }
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let (a, b, c, d) = (
+            texel(x0, y0),
+            texel(x0 + 1, y0),
+            texel(x0, y0 + 1),
+            texel(x0 + 1, y0 + 1),
+        );
+        let mut out = [0.; 4];
+        for i in 0..4 {
+            let top = a[i] + (b[i] - a[i]) * fx;
+            let bottom = c[i] + (d[i] - c[i]) * fx;
+            out[i] = top + (bottom - top) * fy;
+        }
+        out
+    }
+
+    fn blend_factor(factor: GLenum, src: &[f32; 4]) -> f32 {
+        match factor {
+            GL_ZERO => 0.,
+            GL_SRC_ALPHA => src[3],
+            GL_ONE_MINUS_SRC_ALPHA => 1. - src[3],
+            _ => 1.,
+        }
+    }
+
+    fn rasterize_triangle(
+        &mut self,
+        mvp: &Matrix,
+        mut triangle: [&Vertex; 3],
+        clip: Option<[GLint; 4]>,
+    ) {
+        let [vx, vy, vw, vh] = self.viewport.map(|v| v as f32);
+
+        // Window coordinates, origin at the bottom left as in GL.
+        let mut window = triangle.map(|v| {
+            let clip = transform(mvp, [v.pos[0], v.pos[1], v.pos[2], 1.]);
+            [
+                vx + (clip[0] / clip[3] + 1.) * 0.5 * vw,
+                vy + (clip[1] / clip[3] + 1.) * 0.5 * vh,
+            ]
+        });
+
+        let edge = |a: [f32; 2], b: [f32; 2], p: [f32; 2]| {
+            (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+        };
+        let mut area = edge(window[0], window[1], window[2]);
+        if area == 0. {
+            return;
+        }
+        if area < 0. {
+            triangle.swap(1, 2);
+            window.swap(1, 2);
+            area = -area;
+        }
+
+        // Top-left fill rule, so pixels on an edge shared by two triangles are drawn once.
+        let top_left = |a: [f32; 2], b: [f32; 2]| {
+            let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+            dy < 0. || (dy == 0. && dx < 0.)
+        };
+        let bias = [
+            top_left(window[1], window[2]),
+            top_left(window[2], window[0]),
+            top_left(window[0], window[1]),
+        ];
+
+        let mut min_x = 0;
+        let mut min_y = 0;
+        let mut max_x = self.width as i32;
+        let mut max_y = self.height as i32;
+        if let Some([x, y, w, h]) = clip {
+            min_x = min_x.max(x);
+            min_y = min_y.max(y);
+            max_x = max_x.min(x + w);
+            max_y = max_y.min(y + h);
+        }
+        let lo = |i: usize| window.iter().map(|w| w[i]).fold(f32::MAX, f32::min);
+        let hi = |i: usize| window.iter().map(|w| w[i]).fold(f32::MIN, f32::max);
+        min_x = min_x.max(lo(0).floor() as i32);
+        min_y = min_y.max(lo(1).floor() as i32);
+        max_x = max_x.min(hi(0).ceil() as i32);
+        max_y = max_y.min(hi(1).ceil() as i32);
+
+        let texture = if self.current_program != 0 || self.cap_enabled(GL_TEXTURE_2D) {
+            self.textures
+                .get(&self.bound_texture)
+                .filter(|t| !t.rgba.is_empty())
+        } else {
+            None
+        };
+        let blend = self.cap_enabled(GL_BLEND);
+        let (sfactor, dfactor) = self.blend_func;
+
+        let mut writes = Vec::new();
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let p = [x as f32 + 0.5, y as f32 + 0.5];
+                let e = [
+                    edge(window[1], window[2], p),
+                    edge(window[2], window[0], p),
+                    edge(window[0], window[1], p),
+                ];
+                if (0..3).any(|i| e[i] < 0. || (e[i] == 0. && !bias[i])) {
+                    continue;
+                }
+                let (w0, w1, w2) = (e[0] / area, e[1] / area, e[2] / area);
+
+                let mut color = [0.; 4];
+                for (i, c) in color.iter_mut().enumerate() {
+                    *c = (triangle[0].col[i] as f32 * w0
+                        + triangle[1].col[i] as f32 * w1
+                        + triangle[2].col[i] as f32 * w2)
+                        / 255.;
+                }
+
+                if let Some(texture) = texture {
+                    let u =
+                        triangle[0].uv[0] * w0 + triangle[1].uv[0] * w1 + triangle[2].uv[0] * w2;
+                    let v =
+                        triangle[0].uv[1] * w0 + triangle[1].uv[1] * w1 + triangle[2].uv[1] * w2;
+                    let texel = Self::sample(texture, u, v, texture.filter);
+                    for (c, t) in color.iter_mut().zip(texel.iter()) {
+                        *c *= t / 255.;
+                    }
+                }
+
+                writes.push((x as usize, self.height - 1 - y as usize, color));
+            }
+        }
+
+        for (x, row, src) in writes {
+            let i = (row * self.width + x) * 4;
+            let dst = [
+                self.pixels[i] as f32 / 255.,
+                self.pixels[i + 1] as f32 / 255.,
+                self.pixels[i + 2] as f32 / 255.,
+                self.pixels[i + 3] as f32 / 255.,
+            ];
+            let out = if blend {
+                let s = Self::blend_factor(sfactor, &src);
+                let d = Self::blend_factor(dfactor, &src);
+                [0, 1, 2, 3].map(|c| src[c] * s + dst[c] * d)
+            } else {
+                src
+            };
+            for (dst, out) in self.pixels[i..i + 4].iter_mut().zip(out) {
+                *dst = (out.clamp(0., 1.) * 255.).round() as u8;
+            }
+        }
+    }
+}
+
+impl GlBackend for SoftwareGl {
+    fn get_error(&mut self) -> GLenum {
+        std::mem::replace(&mut self.error, GL_NO_ERROR)
+    }
+
+    fn get_integerv(&mut self, pname: GLenum, data: &mut [GLint]) {
+        match pname {
+            GL_TEXTURE_BINDING_2D => data[0] = self.bound_texture as GLint,
+            GL_POLYGON_MODE => {
+                data[0] = self.polygon_mode[0] as GLint;
+                data[1] = self.polygon_mode[1] as GLint;
+            }
+            GL_VIEWPORT => data[..4].copy_from_slice(&self.viewport),
+            GL_SCISSOR_BOX => data[..4].copy_from_slice(&self.scissor),
+            GL_BLEND_SRC => data[0] = self.blend_func.0 as GLint,
+            GL_BLEND_DST => data[0] = self.blend_func.1 as GLint,
+            GL_CURRENT_PROGRAM => data[0] = self.current_program as GLint,
+            _ => data.fill(0),
+        }
+    }
+
+    fn gen_texture(&mut self) -> GLuint {
+        let texture = self.next_texture;
+        self.next_texture += 1;
+        self.textures.insert(
+            texture,
+            Texture {
+                filter: GL_LINEAR,
+                ..Default::default()
+            },
+        );
+        self.commands.push(GlCommand::GenTexture(texture));
+        texture
+    }
+
+    fn delete_texture(&mut self, texture: GLuint) {
+        self.textures.remove(&texture);
+        if self.bound_texture == texture {
+            self.bound_texture = 0;
+        }
+        self.commands.push(GlCommand::DeleteTexture(texture));
+    }
+
+    fn bind_texture(&mut self, target: GLenum, texture: GLuint) {
+        self.bound_texture = texture;
+        self.commands
+            .push(GlCommand::BindTexture { target, texture });
+    }
+
+    fn tex_parameteri(&mut self, target: GLenum, pname: GLenum, param: GLint) {
+        if let Some(texture) = self.textures.get_mut(&self.bound_texture) {
+            if pname == GL_TEXTURE_MAG_FILTER {
+                texture.filter = param as GLenum;
+            }
+        }
+        self.commands.push(GlCommand::TexParameteri {
+            target,
+            pname,
+            param,
+        });
+    }
+
+    fn tex_image_2d(
+        &mut self,
+        _target: GLenum,
+        level: GLint,
+        internal_format: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+        _ty: GLenum,
+        pixels: &[u8],
+    ) {
+        self.commands.push(GlCommand::TexImage2D {
+            texture: self.bound_texture,
+            level,
+            internal_format,
+            width,
+            height,
+            format,
+        });
+        let bytes_per_pixel = match format {
+            GL_ALPHA => 1,
+            GL_RGBA => 4,
+            _ => return self.set_error(GL_INVALID_ENUM),
+        };
+        if width < 0 || height < 0 || pixels.len() < (width * height) as usize * bytes_per_pixel {
+            return self.set_error(GL_INVALID_VALUE);
+        }
+        let Some(texture) = self.textures.get_mut(&self.bound_texture) else {
+            return self.set_error(GL_INVALID_OPERATION);
+        };
+        if level != 0 {
+            return;
+        }
+
+        texture.width = width as usize;
+        texture.height = height as usize;
+        texture.rgba = match format {
+            GL_ALPHA => pixels.iter().flat_map(|a| [255, 255, 255, *a]).collect(),
+            _ => pixels.to_vec(),
+        };
+    }
+
+    fn generate_mipmap(&mut self, target: GLenum) {
+        self.commands.push(GlCommand::GenerateMipmap(target));
+    }
+
+    fn use_program(&mut self, program: GLuint) {
+        self.current_program = program;
+        self.commands.push(GlCommand::UseProgram(program));
+    }
+
+    fn enable(&mut self, cap: GLenum) {
+        if !self.enabled.contains(&cap) {
+            self.enabled.push(cap);
+        }
+        self.commands.push(GlCommand::Enable(cap));
+    }
+
+    fn disable(&mut self, cap: GLenum) {
+        self.enabled.retain(|c| *c != cap);
+        self.commands.push(GlCommand::Disable(cap));
+    }
+
+    fn is_enabled(&mut self, cap: GLenum) -> bool {
+        self.cap_enabled(cap)
+    }
+
+    fn blend_func(&mut self, sfactor: GLenum, dfactor: GLenum) {
+        self.blend_func = (sfactor, dfactor);
+        self.commands
+            .push(GlCommand::BlendFunc { sfactor, dfactor });
+    }
+
+    fn enable_client_state(&mut self, array: GLenum) {
+        self.commands.push(GlCommand::EnableClientState(array));
+    }
+
+    fn disable_client_state(&mut self, array: GLenum) {
+        self.commands.push(GlCommand::DisableClientState(array));
+    }
+
+    fn polygon_mode(&mut self, face: GLenum, mode: GLenum) {
+        match face {
+            GL_FRONT => self.polygon_mode[0] = mode,
+            GL_BACK => self.polygon_mode[1] = mode,
+            _ => self.polygon_mode = [mode, mode],
+        }
+        self.commands.push(GlCommand::PolygonMode { face, mode });
+    }
+
+    fn viewport(&mut self, x: GLint, y: GLint, width: GLsizei, height: GLsizei) {
+        self.viewport = [x, y, width, height];
+        self.commands.push(GlCommand::Viewport(self.viewport));
+    }
+
+    fn scissor(&mut self, x: GLint, y: GLint, width: GLsizei, height: GLsizei) {
+        self.scissor = [x, y, width, height];
+        self.commands.push(GlCommand::Scissor(self.scissor));
+    }
+
+    fn matrix_mode(&mut self, mode: GLenum) {
+        self.matrix_mode = mode;
+        self.commands.push(GlCommand::MatrixMode(mode));
+    }
+
+    fn push_matrix(&mut self) {
+        let top = *self.current_matrix();
+        if self.matrix_mode == GL_PROJECTION {
+            self.projection.push(top);
+        } else {
+            self.modelview.push(top);
+        }
+        self.commands.push(GlCommand::PushMatrix);
+    }
+
+    fn pop_matrix(&mut self) {
+        let stack = if self.matrix_mode == GL_PROJECTION {
+            &mut self.projection
+        } else {
+            &mut self.modelview
+        };
+        if stack.len() > 1 {
+            stack.pop();
+        }
+        self.commands.push(GlCommand::PopMatrix);
+    }
+
+    fn load_identity(&mut self) {
+        *self.current_matrix() = IDENTITY;
+        self.commands.push(GlCommand::LoadIdentity);
+    }
+
+    fn ortho(&mut self, left: f64, right: f64, bottom: f64, top: f64, near: f64, far: f64) {
+        let (l, r, b, t, n, f) = (
+            left as f32,
+            right as f32,
+            bottom as f32,
+            top as f32,
+            near as f32,
+            far as f32,
+        );
+        #[rustfmt::skip]
+        let ortho = [
+            2. / (r - l), 0., 0., 0.,
+            0., 2. / (t - b), 0., 0.,
+            0., 0., -2. / (f - n), 0.,
+            -(r + l) / (r - l), -(t + b) / (t - b), -(f + n) / (f - n), 1.,
+        ];
+        let current = self.current_matrix();
+        *current = mul(current, &ortho);
+        self.commands
+            .push(GlCommand::Ortho([left, right, bottom, top, near, far]));
+    }
+
+    unsafe fn index_pointer_mapped(&mut self, pointer: *const c_void) {
+        self.index_pointer = pointer as *const u16;
+        self.commands.push(GlCommand::IndexPointerMapped);
+    }
+
+    unsafe fn vertex_pointer_mapped(&mut self, size: GLint, pointer: *const c_void) {
+        self.vertex_pointer = ArrayPointer::packed(size, GL_FLOAT, pointer);
+        self.commands.push(GlCommand::VertexPointerMapped { size });
+    }
+
+    unsafe fn tex_coord_pointer_mapped(&mut self, pointer: *const c_void) {
+        self.tex_coord_pointer = ArrayPointer::packed(2, GL_FLOAT, pointer);
+        self.commands.push(GlCommand::TexCoordPointerMapped);
+    }
+
+    unsafe fn color_pointer_mapped(&mut self, ty: GLenum, pointer: *const c_void) {
+        self.color_pointer = ArrayPointer::packed(4, ty, pointer);
+        self.commands.push(GlCommand::ColorPointerMapped { ty });
+    }
+
+    unsafe fn vertex_attrib_pointer_mapped(&mut self, index: GLuint, pointer: *const c_void) {
+        let (size, ty) = self
+            .programs
+            .get(&self.current_program)
+            .and_then(|program| program.attrib_formats.get(&index).copied())
+            .unwrap_or((4, GL_FLOAT));
+        self.attribs
+            .insert(index, ArrayPointer::packed(size, ty, pointer));
+        self.commands
+            .push(GlCommand::VertexAttribPointerMapped { index });
+    }
+
+    unsafe fn draw_objects(&mut self, mode: GLenum, count: GLsizei, _implicit_wvp: bool) {
+        let indices: Vec<usize> = if self.index_pointer.is_null() {
+            Vec::new()
+        } else {
+            (0..count as usize)
+                .map(|i| *self.index_pointer.add(i) as usize)
+                .collect()
+        };
+        let (scissor, vertices) = self.draw(mode, &indices);
+        self.commands.push(GlCommand::DrawObjects {
+            mode,
+            texture: self.bound_texture,
+            scissor,
+            vertices,
+        });
+    }
+
+    unsafe fn vertex_pointer(
+        &mut self,
+        size: GLint,
+        ty: GLenum,
+        stride: GLsizei,
+        pointer: *const c_void,
+    ) {
+        self.vertex_pointer = ArrayPointer::strided(size, ty, stride, pointer);
+        self.commands
+            .push(GlCommand::VertexPointer { size, ty, stride });
+    }
+
+    unsafe fn tex_coord_pointer(
+        &mut self,
+        size: GLint,
+        ty: GLenum,
+        stride: GLsizei,
+        pointer: *const c_void,
+    ) {
+        self.tex_coord_pointer = ArrayPointer::strided(size, ty, stride, pointer);
+        self.commands
+            .push(GlCommand::TexCoordPointer { size, ty, stride });
+    }
+
+    unsafe fn color_pointer(
+        &mut self,
+        size: GLint,
+        ty: GLenum,
+        stride: GLsizei,
+        pointer: *const c_void,
+    ) {
+        self.color_pointer = ArrayPointer::strided(size, ty, stride, pointer);
+        self.commands
+            .push(GlCommand::ColorPointer { size, ty, stride });
+    }
+
+    unsafe fn draw_elements(
+        &mut self,
+        mode: GLenum,
+        count: GLsizei,
+        ty: GLenum,
+        indices: *const c_void,
+    ) {
+        let indices: Vec<usize> = if ty == GL_UNSIGNED_INT {
+            let indices = indices as *const u32;
+            (0..count as usize)
+                .map(|i| indices.add(i).read_unaligned() as usize)
+                .collect()
+        } else {
+            let indices = indices as *const u16;
+            (0..count as usize)
+                .map(|i| indices.add(i).read_unaligned() as usize)
+                .collect()
+        };
+        let (scissor, vertices) = self.draw(mode, &indices);
+        self.commands.push(GlCommand::DrawElements {
+            mode,
+            ty,
+            texture: self.bound_texture,
+            scissor,
+            vertices,
+        });
+    }
+
+    fn create_shader(&mut self, ty: GLenum) -> GLuint {
+        let shader = self.next_object;
+        self.next_object += 1;
+        self.commands.push(GlCommand::CreateShader { shader, ty });
+        shader
+    }
+
+    fn shader_source(&mut self, shader: GLuint, source: &str) {
+        self.commands.push(GlCommand::ShaderSource {
+            shader,
+            source: source.to_string(),
+        });
+    }
+
+    fn compile_shader(&mut self, shader: GLuint) {
+        self.commands.push(GlCommand::CompileShader(shader));
+    }
+
+    fn get_shaderiv(&mut self, _shader: GLuint, pname: GLenum) -> GLint {
+        match pname {
+            GL_COMPILE_STATUS => GL_TRUE as GLint,
+            _ => 0,
+        }
+    }
+
+    fn get_shader_info_log(&mut self, _shader: GLuint) -> String {
+        String::new()
+    }
+
+    fn delete_shader(&mut self, shader: GLuint) {
+        self.commands.push(GlCommand::DeleteShader(shader));
+    }
+
+    fn create_program(&mut self) -> GLuint {
+        let program = self.next_object;
+        self.next_object += 1;
+        self.programs.insert(program, Program::default());
+        self.commands.push(GlCommand::CreateProgram(program));
+        program
+    }
+
+    fn attach_shader(&mut self, program: GLuint, shader: GLuint) {
+        self.commands
+            .push(GlCommand::AttachShader { program, shader });
+    }
+
+    fn bind_attrib_location(
+        &mut self,
+        program: GLuint,
+        index: GLuint,
+        name: &str,
+        size: GLint,
+        ty: GLenum,
+    ) {
+        if let Some(program) = self.programs.get_mut(&program) {
+            program.attrib_formats.insert(index, (size, ty));
+        }
+        self.commands.push(GlCommand::BindAttribLocation {
+            program,
+            index,
+            name: name.to_string(),
+            size,
+            ty,
+        });
+    }
+
+    fn link_program(&mut self, program: GLuint) {
+        self.commands.push(GlCommand::LinkProgram(program));
+    }
+
+    fn get_programiv(&mut self, _program: GLuint, pname: GLenum) -> GLint {
+        match pname {
+            GL_LINK_STATUS => GL_TRUE as GLint,
+            _ => 0,
+        }
+    }
+
+    fn get_program_info_log(&mut self, _program: GLuint) -> String {
+        String::new()
+    }
+
+    fn delete_program(&mut self, program: GLuint) {
+        self.programs.remove(&program);
+        if self.current_program == program {
+            self.current_program = 0;
+        }
+        self.commands.push(GlCommand::DeleteProgram(program));
+    }
+
+    fn get_uniform_location(&mut self, program: GLuint, name: &str) -> GLint {
+        let Some(program) = self.programs.get_mut(&program) else {
+            return -1;
+        };
+        let location = match program.uniforms.iter().position(|u| u == name) {
+            Some(location) => location,
+            None => {
+                program.uniforms.push(name.to_string());
+                program.uniforms.len() - 1
+            }
+        };
+        location as GLint
+    }
+
+    fn uniform_matrix4fv(&mut self, location: GLint, value: &[f32; 16]) {
+        if let Some(program) = self.programs.get_mut(&self.current_program) {
+            program.matrices.insert(location, *value);
+        }
+        self.commands.push(GlCommand::UniformMatrix4fv {
+            location,
+            value: *value,
+        });
+    }
+
+    fn uniform1i(&mut self, location: GLint, value: GLint) {
+        self.commands.push(GlCommand::Uniform1i { location, value });
+    }
+
+    fn enable_vertex_attrib_array(&mut self, index: GLuint) {
+        self.commands
+            .push(GlCommand::EnableVertexAttribArray(index));
+    }
+
+    fn disable_vertex_attrib_array(&mut self, index: GLuint) {
+        self.commands
+            .push(GlCommand::DisableVertexAttribArray(index));
+    }
+
+    unsafe fn vertex_attrib_pointer(
+        &mut self,
+        index: GLuint,
+        size: GLint,
+        ty: GLenum,
+        normalized: bool,
+        stride: GLsizei,
+        pointer: *const c_void,
+    ) {
+        self.attribs
+            .insert(index, ArrayPointer::strided(size, ty, stride, pointer));
+        self.commands.push(GlCommand::VertexAttribPointer {
+            index,
+            size,
+            ty,
+            normalized,
+            stride,
+        });
+    }
+}
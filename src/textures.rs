@@ -0,0 +1,126 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use imgui::TextureId;
+
+use crate::gl::*;
+
+/// Pixel data for `ImguiRenderer::insert_texture`, rows top to bottom.
+#[derive(Clone, Copy, Debug)]
+pub enum TextureData<'a> {
+    Rgba(&'a [u8]),
+    Alpha(&'a [u8]),
+    /// One byte per pixel indexing `palette`, expanded to RGBA on upload. Indices past the end
+    /// of the palette are transparent.
+    Paletted {
+        indices: &'a [u8],
+        palette: &'a [[u8; 4]],
+    },
+}
+
+impl TextureData<'_> {
+    /// The number of bytes given.
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Self::Rgba(pixels) | Self::Alpha(pixels) => pixels.len(),
+            Self::Paletted { indices, .. } => indices.len(),
+        }
+    }
+
+    /// The number of bytes given for each pixel.
+    pub(crate) fn bytes_per_pixel(&self) -> usize {
+        match self {
+            Self::Rgba(_) => 4,
+            Self::Alpha(_) | Self::Paletted { .. } => 1,
+        }
+    }
+
+    /// The GL format and bytes to upload.
+    pub(crate) fn pixels(&self) -> (GLenum, Cow<'_, [u8]>) {
+        match *self {
+            Self::Rgba(pixels) => (GL_RGBA, Cow::Borrowed(pixels)),
+            Self::Alpha(pixels) => (GL_ALPHA, Cow::Borrowed(pixels)),
+            Self::Paletted { indices, palette } => (
+                GL_RGBA,
+                indices
+                    .iter()
+                    .flat_map(|i| palette.get(*i as usize).copied().unwrap_or([0; 4]))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// How textures are sampled when drawn at another size than their own.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextureFilter {
+    /// Nearest neighbor, for pixel art and bitmap fonts drawn 1:1.
+    Nearest,
+    #[default]
+    Linear,
+}
+
+impl TextureFilter {
+    /// The `GL_TEXTURE_MIN_FILTER` and `GL_TEXTURE_MAG_FILTER` values.
+    pub(crate) fn gl_filters(self, mipmaps: bool) -> (GLenum, GLenum) {
+        match (self, mipmaps) {
+            (Self::Nearest, false) => (GL_NEAREST, GL_NEAREST),
+            (Self::Nearest, true) => (GL_NEAREST_MIPMAP_NEAREST, GL_NEAREST),
+            (Self::Linear, false) => (GL_LINEAR, GL_LINEAR),
+            (Self::Linear, true) => (GL_LINEAR_MIPMAP_LINEAR, GL_LINEAR),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Texture {
+    pub name: GLuint,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The GL textures the renderer can draw, keyed by the `TextureId`s handed to ImGui. Ids start
+/// at 1 so a null `TextureId` never resolves.
+#[derive(Debug, Default)]
+pub struct Textures {
+    textures: BTreeMap<usize, Texture>,
+    next: usize,
+}
+
+impl Textures {
+    pub fn get(&self, id: TextureId) -> Option<&Texture> {
+        self.textures.get(&id.id())
+    }
+
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (TextureId, &Texture)> {
+        self.textures
+            .iter()
+            .map(|(id, texture)| (TextureId::new(*id), texture))
+    }
+
+    pub(crate) fn insert(&mut self, texture: Texture) -> TextureId {
+        self.next += 1;
+        self.textures.insert(self.next, texture);
+        TextureId::new(self.next)
+    }
+
+    pub(crate) fn get_mut(&mut self, id: TextureId) -> Option<&mut Texture> {
+        self.textures.get_mut(&id.id())
+    }
+
+    pub(crate) fn remove(&mut self, id: TextureId) -> Option<Texture> {
+        self.textures.remove(&id.id())
+    }
+
+    pub(crate) fn drain(&mut self) -> impl Iterator<Item = Texture> {
+        std::mem::take(&mut self.textures).into_values()
+    }
+}
@@ -0,0 +1,381 @@
+use std::ffi::{c_void, CString};
+use std::mem::zeroed;
+
+use vitagl_sys::*;
+use vitasdk_sys::psp2::kernel::processmgr::sceKernelGetProcessTimeWide;
+use vitasdk_sys::psp2::{ctrl::*, touch::*};
+use vitasdk_sys::psp2common::ctrl::*;
+
+use crate::backend::{
+    Clock, CtrlMode, GlBackend, InputBackend, PadState, TouchPanel, TouchPoint, TouchPorts,
+    TouchState, MAX_TOUCH_POINTS,
+};
+
+/// `GlBackend` calling straight into vitaGL.
+#[derive(Default)]
+pub struct VitaGl;
+
+impl GlBackend for VitaGl {
+    fn get_error(&mut self) -> u32 {
+        unsafe { glGetError() }
+    }
+
+    fn get_integerv(&mut self, pname: u32, data: &mut [i32]) {
+        unsafe { glGetIntegerv(pname, data.as_mut_ptr()) }
+    }
+
+    fn gen_texture(&mut self) -> u32 {
+        let mut texture = 0;
+        unsafe { glGenTextures(1, &mut texture) };
+        texture
+    }
+
+    fn delete_texture(&mut self, texture: u32) {
+        unsafe { glDeleteTextures(1, &texture) }
+    }
+
+    fn bind_texture(&mut self, target: u32, texture: u32) {
+        unsafe { glBindTexture(target, texture) }
+    }
+
+    fn tex_parameteri(&mut self, target: u32, pname: u32, param: i32) {
+        unsafe { glTexParameteri(target, pname, param) }
+    }
+
+    fn tex_image_2d(
+        &mut self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        ty: u32,
+        pixels: &[u8],
+    ) {
+        unsafe {
+            glTexImage2D(
+                target,
+                level,
+                internal_format,
+                width,
+                height,
+                0,
+                format,
+                ty,
+                pixels.as_ptr() as *const _,
+            )
+        }
+    }
+
+    fn generate_mipmap(&mut self, target: u32) {
+        unsafe { glGenerateMipmap(target) }
+    }
+
+    fn use_program(&mut self, program: u32) {
+        unsafe { glUseProgram(program) }
+    }
+
+    fn enable(&mut self, cap: u32) {
+        unsafe { glEnable(cap) }
+    }
+
+    fn disable(&mut self, cap: u32) {
+        unsafe { glDisable(cap) }
+    }
+
+    fn is_enabled(&mut self, cap: u32) -> bool {
+        unsafe { glIsEnabled(cap) != 0 }
+    }
+
+    fn blend_func(&mut self, sfactor: u32, dfactor: u32) {
+        unsafe { glBlendFunc(sfactor, dfactor) }
+    }
+
+    fn enable_client_state(&mut self, array: u32) {
+        unsafe { glEnableClientState(array) }
+    }
+
+    fn disable_client_state(&mut self, array: u32) {
+        unsafe { glDisableClientState(array) }
+    }
+
+    fn polygon_mode(&mut self, face: u32, mode: u32) {
+        unsafe { glPolygonMode(face, mode) }
+    }
+
+    fn viewport(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        unsafe { glViewport(x, y, width, height) }
+    }
+
+    fn scissor(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        unsafe { glScissor(x, y, width, height) }
+    }
+
+    fn matrix_mode(&mut self, mode: u32) {
+        unsafe { glMatrixMode(mode) }
+    }
+
+    fn push_matrix(&mut self) {
+        unsafe { glPushMatrix() }
+    }
+
+    fn pop_matrix(&mut self) {
+        unsafe { glPopMatrix() }
+    }
+
+    fn load_identity(&mut self) {
+        unsafe { glLoadIdentity() }
+    }
+
+    fn ortho(&mut self, left: f64, right: f64, bottom: f64, top: f64, near: f64, far: f64) {
+        unsafe { glOrtho(left, right, bottom, top, near, far) }
+    }
+
+    unsafe fn index_pointer_mapped(&mut self, pointer: *const c_void) {
+        vglIndexPointerMapped(pointer);
+    }
+
+    unsafe fn vertex_pointer_mapped(&mut self, size: i32, pointer: *const c_void) {
+        vglVertexPointerMapped(size, pointer);
+    }
+
+    unsafe fn tex_coord_pointer_mapped(&mut self, pointer: *const c_void) {
+        vglTexCoordPointerMapped(pointer);
+    }
+
+    unsafe fn color_pointer_mapped(&mut self, ty: u32, pointer: *const c_void) {
+        vglColorPointerMapped(ty, pointer);
+    }
+
+    unsafe fn vertex_attrib_pointer_mapped(&mut self, index: u32, pointer: *const c_void) {
+        vglVertexAttribPointerMapped(index as _, pointer);
+    }
+
+    unsafe fn draw_objects(&mut self, mode: u32, count: i32, implicit_wvp: bool) {
+        vglDrawObjects(mode, count, implicit_wvp as u8)
+    }
+
+    unsafe fn vertex_pointer(&mut self, size: i32, ty: u32, stride: i32, pointer: *const c_void) {
+        glVertexPointer(size, ty, stride, pointer);
+    }
+
+    unsafe fn tex_coord_pointer(
+        &mut self,
+        size: i32,
+        ty: u32,
+        stride: i32,
+        pointer: *const c_void,
+    ) {
+        glTexCoordPointer(size, ty, stride, pointer);
+    }
+
+    unsafe fn color_pointer(&mut self, size: i32, ty: u32, stride: i32, pointer: *const c_void) {
+        glColorPointer(size, ty, stride, pointer);
+    }
+
+    unsafe fn draw_elements(&mut self, mode: u32, count: i32, ty: u32, indices: *const c_void) {
+        glDrawElements(mode, count, ty, indices);
+    }
+
+    fn create_shader(&mut self, ty: u32) -> u32 {
+        unsafe { glCreateShader(ty) }
+    }
+
+    fn shader_source(&mut self, shader: u32, source: &str) {
+        let source = CString::new(source).unwrap();
+        unsafe { glShaderSource(shader, 1, &source.as_ptr(), std::ptr::null()) }
+    }
+
+    fn compile_shader(&mut self, shader: u32) {
+        unsafe { glCompileShader(shader) }
+    }
+
+    fn get_shaderiv(&mut self, shader: u32, pname: u32) -> i32 {
+        let mut param = 0;
+        unsafe { glGetShaderiv(shader, pname, &mut param) };
+        param
+    }
+
+    fn get_shader_info_log(&mut self, shader: u32) -> String {
+        let mut log = [0u8; 1024];
+        let mut length = 0;
+        unsafe {
+            glGetShaderInfoLog(
+                shader,
+                log.len() as i32,
+                &mut length,
+                log.as_mut_ptr() as *mut _,
+            )
+        };
+        String::from_utf8_lossy(&log[..length as usize]).into_owned()
+    }
+
+    fn delete_shader(&mut self, shader: u32) {
+        unsafe { glDeleteShader(shader) }
+    }
+
+    fn create_program(&mut self) -> u32 {
+        unsafe { glCreateProgram() }
+    }
+
+    fn attach_shader(&mut self, program: u32, shader: u32) {
+        unsafe { glAttachShader(program, shader) }
+    }
+
+    fn bind_attrib_location(&mut self, program: u32, index: u32, name: &str, size: i32, ty: u32) {
+        let name = CString::new(name).unwrap();
+        unsafe { vglBindAttribLocation(program, index, name.as_ptr(), size as u32, ty) }
+    }
+
+    fn link_program(&mut self, program: u32) {
+        unsafe { glLinkProgram(program) }
+    }
+
+    fn get_programiv(&mut self, program: u32, pname: u32) -> i32 {
+        let mut param = 0;
+        unsafe { glGetProgramiv(program, pname, &mut param) };
+        param
+    }
+
+    fn get_program_info_log(&mut self, program: u32) -> String {
+        let mut log = [0u8; 1024];
+        let mut length = 0;
+        unsafe {
+            glGetProgramInfoLog(
+                program,
+                log.len() as i32,
+                &mut length,
+                log.as_mut_ptr() as *mut _,
+            )
+        };
+        String::from_utf8_lossy(&log[..length as usize]).into_owned()
+    }
+
+    fn delete_program(&mut self, program: u32) {
+        unsafe { glDeleteProgram(program) }
+    }
+
+    fn get_uniform_location(&mut self, program: u32, name: &str) -> i32 {
+        let name = CString::new(name).unwrap();
+        unsafe { glGetUniformLocation(program, name.as_ptr()) }
+    }
+
+    fn uniform_matrix4fv(&mut self, location: i32, value: &[f32; 16]) {
+        unsafe { glUniformMatrix4fv(location, 1, GL_FALSE as u8, value.as_ptr()) }
+    }
+
+    fn uniform1i(&mut self, location: i32, value: i32) {
+        unsafe { glUniform1i(location, value) }
+    }
+
+    fn enable_vertex_attrib_array(&mut self, index: u32) {
+        unsafe { glEnableVertexAttribArray(index) }
+    }
+
+    fn disable_vertex_attrib_array(&mut self, index: u32) {
+        unsafe { glDisableVertexAttribArray(index) }
+    }
+
+    unsafe fn vertex_attrib_pointer(
+        &mut self,
+        index: u32,
+        size: i32,
+        ty: u32,
+        normalized: bool,
+        stride: i32,
+        pointer: *const c_void,
+    ) {
+        glVertexAttribPointer(index, size, ty, normalized as u8, stride, pointer);
+    }
+}
+
+/// `InputBackend` reading the touch panels and the controller through vitasdk.
+#[derive(Default)]
+pub struct VitaInput;
+
+impl InputBackend for VitaInput {
+    fn init(&mut self, touch_ports: TouchPorts, ctrl_mode: CtrlMode) {
+        unsafe {
+            if touch_ports.front() {
+                sceTouchSetSamplingState(
+                    SceTouchPortType::SCE_TOUCH_PORT_FRONT,
+                    SceTouchSamplingState::SCE_TOUCH_SAMPLING_STATE_START,
+                );
+            }
+            if touch_ports.back() {
+                sceTouchSetSamplingState(
+                    SceTouchPortType::SCE_TOUCH_PORT_BACK,
+                    SceTouchSamplingState::SCE_TOUCH_SAMPLING_STATE_START,
+                );
+            }
+            sceCtrlSetSamplingMode(match ctrl_mode {
+                CtrlMode::Digital => SceCtrlPadInputMode::SCE_CTRL_MODE_DIGITAL,
+                CtrlMode::Analog => SceCtrlPadInputMode::SCE_CTRL_MODE_ANALOG,
+                CtrlMode::AnalogWide => SceCtrlPadInputMode::SCE_CTRL_MODE_ANALOG_WIDE,
+            });
+        }
+    }
+
+    fn read_pad(&mut self) -> PadState {
+        unsafe {
+            let mut pad = zeroed::<SceCtrlData>();
+            sceCtrlPeekBufferPositive(0, &mut pad, 1);
+            PadState {
+                buttons: pad.buttons,
+                lx: pad.lx,
+                ly: pad.ly,
+                rx: pad.rx,
+                ry: pad.ry,
+            }
+        }
+    }
+
+    fn read_touch(&mut self, panel: TouchPanel) -> TouchState {
+        let port = match panel {
+            TouchPanel::Front => SceTouchPortType::SCE_TOUCH_PORT_FRONT,
+            TouchPanel::Back => SceTouchPortType::SCE_TOUCH_PORT_BACK,
+        };
+
+        let mut state = TouchState::default();
+        unsafe {
+            let mut data = zeroed::<SceTouchData>();
+            if sceTouchPeek(port, &mut data, 1) < 0 {
+                return state;
+            }
+            state.count = (data.reportNum as usize).min(MAX_TOUCH_POINTS);
+            for (point, report) in state.points.iter_mut().zip(&data.report[..state.count]) {
+                *point = TouchPoint::from_report(panel, report.id, report.x, report.y);
+            }
+        }
+        state
+    }
+}
+
+/// `Clock` backed by `sceKernelGetProcessTimeWide`.
+#[derive(Default)]
+pub struct VitaClock;
+
+impl Clock for VitaClock {
+    fn now_micros(&mut self) -> u64 {
+        unsafe { sceKernelGetProcessTimeWide() }
+    }
+}
+
+#[link(name = "vitaGL", kind = "static")]
+extern "C" {}
+
+#[link(name = "vitashark", kind = "static")]
+extern "C" {}
+
+#[link(name = "SceShaccCg_stub", kind = "static")]
+extern "C" {}
+
+#[link(name = "SceShaccCgExt", kind = "static")]
+extern "C" {}
+
+#[link(name = "taihen_stub", kind = "static")]
+extern "C" {}
+
+#[link(name = "mathneon", kind = "static")]
+extern "C" {}
@@ -0,0 +1,84 @@
+//! Checks that the renderer frees exactly what it allocates through ImGui's allocator. The
+//! allocator is process wide, so this file holds a single test.
+
+use std::alloc::Layout;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::sync::Mutex;
+
+use imgui_rs_vitagl_renderer::software::SoftwareGl;
+use imgui_rs_vitagl_renderer::{ImguiRendererBuilder, OverflowPolicy};
+
+/// Live allocations by base pointer, and frees of pointers that are not one.
+static LIVE: Mutex<Option<HashMap<usize, Layout>>> = Mutex::new(None);
+static BAD_FREES: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+static ALLOCATIONS: Mutex<usize> = Mutex::new(0);
+
+unsafe extern "C" fn alloc(size: usize, _: *mut c_void) -> *mut c_void {
+    let layout = Layout::from_size_align(size.max(1), 16).unwrap();
+    let ptr = std::alloc::alloc(layout);
+    LIVE.lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(ptr as usize, layout);
+    *ALLOCATIONS.lock().unwrap() += 1;
+    ptr as *mut c_void
+}
+
+unsafe extern "C" fn free(ptr: *mut c_void, _: *mut c_void) {
+    if ptr.is_null() {
+        return;
+    }
+    let layout = LIVE
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .remove(&(ptr as usize));
+    match layout {
+        Some(layout) => std::alloc::dealloc(ptr as *mut u8, layout),
+        None => BAD_FREES.lock().unwrap().push(ptr as usize),
+    }
+}
+
+fn live() -> usize {
+    LIVE.lock().unwrap().as_ref().map_or(0, HashMap::len)
+}
+
+#[test]
+fn renderer_frees_its_allocations() {
+    unsafe { imgui::sys::igSetAllocatorFunctions(Some(alloc), Some(free), std::ptr::null_mut()) };
+
+    let mut ctx = imgui::Context::create();
+    ctx.set_ini_filename(None);
+    ctx.io_mut().display_size = [960., 544.];
+
+    // The small pools need several times their size for the demo window, so `Grow` reallocates.
+    let builders = [
+        (ImguiRendererBuilder::new(), false),
+        (
+            ImguiRendererBuilder::new()
+                .index_buffer_len(1100)
+                .mempool_size(3300)
+                .overflow_policy(OverflowPolicy::Grow),
+            true,
+        ),
+    ];
+    for (builder, grows) in builders {
+        let mut renderer = builder
+            .build_with_backend(&mut ctx, SoftwareGl::new(960, 544))
+            .unwrap();
+        renderer.prepare_frame(ctx.fonts()).unwrap();
+        ctx.new_frame().show_demo_window(&mut true);
+        let draw_data = ctx.render();
+
+        let before = *ALLOCATIONS.lock().unwrap();
+        renderer.render(draw_data).unwrap();
+        let during = *ALLOCATIONS.lock().unwrap() - before;
+        assert_eq!(during > 0, grows);
+        drop(renderer);
+    }
+
+    drop(ctx);
+    assert_eq!(*BAD_FREES.lock().unwrap(), Vec::<usize>::new());
+    assert_eq!(live(), 0, "allocations leaked");
+}
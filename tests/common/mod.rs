@@ -0,0 +1,103 @@
+//! Helpers shared by the integration tests.
+//!
+//! imgui has a single current context per process, so every test creating one holds the lock
+//! from `lock_imgui` until the context is dropped.
+//!
+//! Golden images live in `tests/golden` as 8-bit RGBA PNGs and are compared with
+//! `SoftwareGl::mismatched_pixels`. Running the tests with `UPDATE_GOLDENS=1` rewrites them from
+//! the current frames.
+#![allow(dead_code)]
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use imgui_rs_vitagl_renderer::software::SoftwareGl;
+use imgui_rs_vitagl_renderer::{ImguiRenderer, ImguiRendererBuilder};
+
+static IMGUI_LOCK: Mutex<()> = Mutex::new(());
+
+/// Serializes the tests of a binary that create an imgui context. A test that failed while
+/// holding the lock does not fail the others.
+pub fn lock_imgui() -> MutexGuard<'static, ()> {
+    IMGUI_LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A fresh context with a 64x64 display and a renderer built by `builder` that draws it into a
+/// 64x64 `SoftwareGl`, with the imgui lock held for them. Drop the renderer before the context,
+/// and both before the lock.
+pub fn software_renderer(
+    builder: ImguiRendererBuilder,
+) -> (
+    MutexGuard<'static, ()>,
+    imgui::Context,
+    ImguiRenderer<SoftwareGl>,
+) {
+    let lock = lock_imgui();
+    let mut ctx = imgui::Context::create();
+    ctx.set_ini_filename(None);
+    ctx.io_mut().display_size = [64., 64.];
+    let renderer = builder
+        .build_with_backend(&mut ctx, SoftwareGl::new(64, 64))
+        .unwrap();
+    (lock, ctx, renderer)
+}
+
+/// Compares the framebuffer of `gl` with `tests/golden/<name>.png`, allowing each channel to be
+/// off by 2. A mismatching frame is written to `<name>.actual.png` in the test tmp dir for
+/// inspection.
+pub fn assert_golden(name: &str, gl: &SoftwareGl) {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.png", name));
+    if std::env::var_os("UPDATE_GOLDENS").is_some() {
+        write_png(&golden, gl.width(), gl.height(), gl.pixels());
+        return;
+    }
+
+    let (width, height, expected) = read_png(&golden);
+    assert_eq!(
+        (width, height),
+        (gl.width(), gl.height()),
+        "{} has a different size",
+        golden.display()
+    );
+    let mismatched = gl.mismatched_pixels(&expected, 2);
+    if mismatched > 0 {
+        let actual =
+            PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{}.actual.png", name));
+        write_png(&actual, gl.width(), gl.height(), gl.pixels());
+        panic!(
+            "{} pixels differ from {}, frame written to {}",
+            mismatched,
+            golden.display(),
+            actual.display()
+        );
+    }
+}
+
+/// Reads an 8-bit RGBA PNG.
+pub fn read_png(path: &Path) -> (usize, usize, Vec<u8>) {
+    let file = File::open(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    let mut reader = png::Decoder::new(file).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!(
+        (info.color_type, info.bit_depth),
+        (png::ColorType::Rgba, png::BitDepth::Eight),
+        "{} is not 8-bit RGBA",
+        path.display()
+    );
+    pixels.truncate(info.buffer_size());
+    (info.width as usize, info.height as usize, pixels)
+}
+
+pub fn write_png(path: &Path, width: usize, height: usize, rgba: &[u8]) {
+    let file = BufWriter::new(File::create(path).unwrap());
+    let mut encoder = png::Encoder::new(file, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(rgba).unwrap();
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use imgui::sys::{ImFont, ImFont_FindGlyphNoFallback};
+use imgui::FontAtlas;
+use imgui_rs_vitagl_renderer::software::GlCommand;
+use imgui_rs_vitagl_renderer::{GlyphPages, ImguiRendererBuilder};
+
+/// A TrueType font of square glyphs for ' ', '?', 'A' and U+0100, so page 0 and page 1 each
+/// have glyphs to rasterize.
+const SQUARES: &[u8] = include_bytes!("fonts/squares.ttf");
+
+/// Whether the paged font rasterized a glyph for `c`.
+fn has_glyph(fonts: &mut FontAtlas, pages: &GlyphPages, c: char) -> bool {
+    let font = fonts.get_font(pages.font().unwrap()).unwrap() as *const _ as *mut ImFont;
+    !unsafe { ImFont_FindGlyphNoFallback(font, c as _) }.is_null()
+}
+
+#[test]
+fn pages_are_uploaded_and_evicted() {
+    let (_lock, mut ctx, mut renderer) = common::software_renderer(ImguiRendererBuilder::new());
+    let mut pages = GlyphPages::new(SQUARES.to_vec(), 16.).max_pages(2);
+    let mut frame = |text: &str| {
+        pages.request(text);
+        assert!(pages.update(ctx.fonts()));
+        renderer.gl_mut().take_commands();
+        renderer.prepare_frame(ctx.fonts()).unwrap();
+        let uploaded = renderer
+            .gl()
+            .commands()
+            .iter()
+            .any(|command| matches!(command, GlCommand::TexImage2D { .. }));
+        assert!(uploaded, "atlas not uploaded after requesting {:?}", text);
+        [
+            has_glyph(ctx.fonts(), &pages, 'A'),
+            has_glyph(ctx.fonts(), &pages, '\u{100}'),
+        ]
+    };
+
+    // Only page 0 is resident to begin with.
+    assert_eq!(frame(""), [true, false]);
+    assert_eq!(frame("\u{100}"), [true, true]);
+    // Page 2 pushes page 1 out of the two page budget.
+    assert_eq!(frame("\u{200}"), [true, false]);
+}
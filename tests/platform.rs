@@ -0,0 +1,456 @@
+mod common;
+
+use std::sync::MutexGuard;
+
+use imgui::{BackendFlags, Key};
+use imgui_rs_vitagl_renderer::backend::*;
+use imgui_rs_vitagl_renderer::software::SoftwareGl;
+use imgui_rs_vitagl_renderer::{
+    ConfigError, ImguiRendererBuilder, RearTouch, RendererError, VitaPlatform, VitaPlatformBuilder,
+};
+
+/// Reports the pad and touch samples a test sets through `VitaPlatform::input_mut`, with the
+/// sticks at rest and no fingers down to begin with.
+struct ScriptedInput {
+    pad: PadState,
+    front: Vec<[f32; 2]>,
+    back: Vec<[f32; 2]>,
+}
+
+impl ScriptedInput {
+    fn new() -> Self {
+        Self {
+            pad: PadState {
+                lx: 128,
+                ly: 128,
+                rx: 128,
+                ry: 128,
+                ..PadState::default()
+            },
+            front: Vec::new(),
+            back: Vec::new(),
+        }
+    }
+}
+
+impl InputBackend for ScriptedInput {
+    fn init(&mut self, _touch_ports: TouchPorts, _ctrl_mode: CtrlMode) {}
+
+    fn read_pad(&mut self) -> PadState {
+        self.pad
+    }
+
+    fn read_touch(&mut self, panel: TouchPanel) -> TouchState {
+        let fingers = match panel {
+            TouchPanel::Front => &self.front,
+            TouchPanel::Back => &self.back,
+        };
+        let mut state = TouchState::default();
+        for (id, (point, [x, y])) in state.points.iter_mut().zip(fingers).enumerate() {
+            *point = TouchPoint {
+                id: id as u8,
+                x: *x,
+                y: *y,
+            };
+        }
+        state.count = fingers.len();
+        state
+    }
+}
+
+type ScriptedPlatform = VitaPlatform<ScriptedInput, FixedStepClock>;
+
+/// A context with a built font atlas, ready for `new_frame`, and the imgui lock held for it.
+/// Drop the context before the lock.
+fn context() -> (MutexGuard<'static, ()>, imgui::Context) {
+    let lock = common::lock_imgui();
+    let mut ctx = imgui::Context::create();
+    ctx.set_ini_filename(None);
+    ctx.fonts().build_alpha8_texture();
+    (lock, ctx)
+}
+
+fn scripted_platform(ctx: &mut imgui::Context, builder: VitaPlatformBuilder) -> ScriptedPlatform {
+    builder
+        .build_with_backends(ctx, ScriptedInput::new(), FixedStepClock::new(15_625))
+        .unwrap()
+}
+
+/// The mouse as ImGui saw it in a frame.
+#[derive(Debug, PartialEq)]
+struct Mouse {
+    pos: [f32; 2],
+    down: [bool; 2],
+    wheel: [f32; 2],
+}
+
+/// Runs a frame with the platform's current input.
+fn frame(ctx: &mut imgui::Context, platform: &mut ScriptedPlatform) -> Mouse {
+    platform.prepare_frame(ctx.io_mut());
+    let io = ctx.new_frame().io();
+    let mouse = Mouse {
+        pos: io.mouse_pos,
+        down: [io.mouse_down[0], io.mouse_down[1]],
+        wheel: [io.mouse_wheel_h, io.mouse_wheel],
+    };
+    ctx.render();
+    mouse
+}
+
+#[test]
+fn letterbox_touches_are_ignored() {
+    let (_lock, mut ctx) = context();
+    let mut platform = scripted_platform(
+        &mut ctx,
+        VitaPlatformBuilder::new()
+            .display_size([720., 408.])
+            .viewport([120., 68., 720., 408.]),
+    );
+
+    // On the left bar, then in the middle of the viewport.
+    platform.input_mut().front = vec![[0.05, 0.5]];
+    let bar = frame(&mut ctx, &mut platform);
+    assert_eq!((bar.pos, bar.down[0]), ([0., 0.], false));
+    platform.input_mut().front = vec![[0.5, 0.5]];
+    let middle = frame(&mut ctx, &mut platform);
+    assert_eq!((middle.pos, middle.down[0]), ([360., 204.], true));
+}
+
+#[test]
+fn backends_register_once_per_context() {
+    let (_lock, mut ctx) = context();
+    let renderer = ImguiRendererBuilder::new()
+        .build_with_backend(&mut ctx, SoftwareGl::new(64, 64))
+        .unwrap();
+    let mut platform = scripted_platform(&mut ctx, VitaPlatformBuilder::new());
+    let renderer_name = ctx.renderer_name().map(str::to_owned);
+    let platform_name = ctx.platform_name().map(str::to_owned);
+    let flags = BackendFlags::RENDERER_HAS_VTX_OFFSET | BackendFlags::HAS_GAMEPAD;
+    assert!(ctx.io().backend_flags.contains(flags));
+
+    // Cursor shapes are honored while ImGui draws the cursor.
+    assert!(!ctx
+        .io()
+        .backend_flags
+        .contains(BackendFlags::HAS_MOUSE_CURSORS));
+    platform.set_mousestick_usage(true);
+    platform.prepare_frame(ctx.io_mut());
+    assert!(ctx
+        .io()
+        .backend_flags
+        .contains(BackendFlags::HAS_MOUSE_CURSORS));
+    platform.set_mousestick_usage(false);
+    platform.prepare_frame(ctx.io_mut());
+    assert!(!ctx
+        .io()
+        .backend_flags
+        .contains(BackendFlags::HAS_MOUSE_CURSORS));
+    platform.set_mousestick_usage(true);
+    platform.prepare_frame(ctx.io_mut());
+
+    assert!(matches!(
+        ImguiRendererBuilder::new().build_with_backend(&mut ctx, SoftwareGl::new(64, 64)),
+        Err(RendererError::Config(ConfigError::AlreadyInitialized(name)))
+            if Some(&name) == renderer_name.as_ref()
+    ));
+    assert!(matches!(
+        VitaPlatformBuilder::new().build_with_backends(
+            &mut ctx,
+            ScriptedInput::new(),
+            FixedStepClock::new(15_625)
+        ),
+        Err(ConfigError::AlreadyInitialized(name)) if Some(&name) == platform_name.as_ref()
+    ));
+
+    drop(renderer);
+    drop(platform);
+    assert_eq!(ctx.renderer_name(), None);
+    assert_eq!(ctx.platform_name(), None);
+    assert!(!ctx
+        .io()
+        .backend_flags
+        .intersects(flags | BackendFlags::HAS_MOUSE_CURSORS));
+    assert!(!ctx.io().mouse_draw_cursor);
+
+    let _renderer = ImguiRendererBuilder::new()
+        .build_with_backend(&mut ctx, SoftwareGl::new(64, 64))
+        .unwrap();
+    let _platform = scripted_platform(&mut ctx, VitaPlatformBuilder::new());
+    assert_eq!(ctx.renderer_name().map(str::to_owned), renderer_name);
+    assert_eq!(ctx.platform_name().map(str::to_owned), platform_name);
+    assert!(ctx.io().backend_flags.contains(flags));
+}
+
+#[test]
+fn delta_time_is_clamped_and_reset_after_suspend() {
+    let (_lock, mut ctx) = context();
+    let mut platform = scripted_platform(
+        &mut ctx,
+        VitaPlatformBuilder::new()
+            .max_delta_time(0.1)
+            .suspend_threshold(1.),
+    );
+    let mut delta_time = |platform: &mut ScriptedPlatform, advance: u64| {
+        platform.clock_mut().advance(advance);
+        platform.prepare_frame(ctx.io_mut());
+        (ctx.io().delta_time, platform.resumed())
+    };
+
+    assert_eq!(delta_time(&mut platform, 0), (1. / 60., false));
+    assert_eq!(delta_time(&mut platform, 0), (1. / 64., false));
+    // A long frame, then one past the suspend threshold.
+    assert_eq!(delta_time(&mut platform, 500_000), (0.1, false));
+    assert_eq!(delta_time(&mut platform, 2_000_000), (1. / 60., true));
+    assert_eq!(delta_time(&mut platform, 0), (1. / 64., false));
+}
+
+#[test]
+fn zero_delta_time_is_raised_to_the_minimum() {
+    let (_lock, mut ctx) = context();
+    let mut platform = VitaPlatformBuilder::new()
+        .build_with_backends(&mut ctx, ScriptedInput::new(), FixedStepClock::new(0))
+        .unwrap();
+    // A clock at zero reads as not started yet.
+    platform.clock_mut().advance(1_000);
+    platform.prepare_frame(ctx.io_mut());
+    platform.prepare_frame(ctx.io_mut());
+    let delta_time = ctx.io().delta_time;
+    assert!(delta_time > 0. && delta_time <= 1e-6, "{}", delta_time);
+}
+
+#[test]
+fn rear_scroll_follows_the_finger_centroid() {
+    let (_lock, mut ctx) = context();
+    let mut platform = scripted_platform(
+        &mut ctx,
+        VitaPlatformBuilder::new()
+            .touch_ports(TouchPorts::Both)
+            .rear_touch(RearTouch::Scroll)
+            .rear_scroll_speed(10.),
+    );
+    let mut wheel = |fingers: &[[f32; 2]]| {
+        platform.input_mut().back = fingers.to_vec();
+        let [x, y] = frame(&mut ctx, &mut platform).wheel;
+        [(x * 100.).round() / 100., (y * 100.).round() / 100.]
+    };
+
+    assert_eq!(wheel(&[[0.5, 0.5]]), [0., 0.]);
+    // One finger only scrolls vertically.
+    assert_eq!(wheel(&[[0.6, 0.7]]), [0., 2.]);
+    // A second finger landing moves the centroid without scrolling.
+    assert_eq!(wheel(&[[0.6, 0.7], [0.8, 0.7]]), [0., 0.]);
+    assert_eq!(wheel(&[[0.5, 0.8], [0.7, 0.8]]), [-1., 1.]);
+    assert_eq!(wheel(&[[0.5, 0.8]]), [0., 0.]);
+    assert_eq!(wheel(&[]), [0., 0.]);
+}
+
+#[test]
+fn rear_pointer_presses_with_a_second_finger() {
+    let (_lock, mut ctx) = context();
+    let mut platform = scripted_platform(
+        &mut ctx,
+        VitaPlatformBuilder::new()
+            .touch_ports(TouchPorts::Both)
+            .rear_touch(RearTouch::Pointer),
+    );
+    let mut mouse = |front: &[[f32; 2]], back: &[[f32; 2]]| {
+        platform.input_mut().front = front.to_vec();
+        platform.input_mut().back = back.to_vec();
+        let mouse = frame(&mut ctx, &mut platform);
+        (mouse.pos, mouse.down[0])
+    };
+
+    assert_eq!(mouse(&[], &[[0.25, 0.5]]), ([240., 272.], false));
+    assert_eq!(
+        mouse(&[], &[[0.5, 0.5], [0.75, 0.75]]),
+        ([480., 272.], true)
+    );
+    assert_eq!(mouse(&[], &[]), ([480., 272.], false));
+    // The front panel wins while it is pressed.
+    assert_eq!(mouse(&[[0.75, 0.5]], &[[0.25, 0.5]]), ([720., 272.], true));
+    assert_eq!(
+        mouse(&[[0.75, 0.5]], &[[0.25, 0.5], [0.5, 0.5]]),
+        ([720., 272.], true)
+    );
+    assert_eq!(mouse(&[], &[[0.25, 0.5]]), ([240., 272.], false));
+}
+
+/// Runs a frame and returns whether each of `keys` was down and its analog value.
+fn key_frame(
+    ctx: &mut imgui::Context,
+    platform: &mut ScriptedPlatform,
+    keys: &[Key],
+) -> Vec<(bool, f32)> {
+    platform.prepare_frame(ctx.io_mut());
+    ctx.new_frame();
+    let keys_data = unsafe { &(*imgui::sys::igGetIO()).KeysData };
+    let offset = imgui::sys::ImGuiKey_KeysData_OFFSET as usize;
+    let state = keys
+        .iter()
+        .map(|key| {
+            let data = &keys_data[*key as usize - offset];
+            (data.Down, data.AnalogValue)
+        })
+        .collect();
+    ctx.render();
+    state
+}
+
+#[test]
+fn gamepad_buttons_map_to_keys() {
+    let (_lock, mut ctx) = context();
+    let mut platform = scripted_platform(&mut ctx, VitaPlatformBuilder::new());
+    let keys = [
+        Key::GamepadFaceDown,
+        Key::GamepadFaceRight,
+        Key::GamepadDpadLeft,
+        Key::GamepadDpadRight,
+        Key::GamepadDpadUp,
+        Key::GamepadDpadDown,
+    ];
+    let mut down = |buttons: u32| {
+        platform.input_mut().pad.buttons = buttons;
+        key_frame(&mut ctx, &mut platform, &keys)
+            .into_iter()
+            .map(|(pressed, _)| pressed)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(down(0), [false; 6]);
+    assert_eq!(
+        down(SCE_CTRL_CROSS),
+        [true, false, false, false, false, false]
+    );
+    assert_eq!(
+        down(SCE_CTRL_CIRCLE),
+        [false, true, false, false, false, false]
+    );
+    assert_eq!(
+        down(SCE_CTRL_LEFT | SCE_CTRL_UP),
+        [false, false, true, false, true, false]
+    );
+    assert_eq!(
+        down(SCE_CTRL_RIGHT | SCE_CTRL_DOWN),
+        [false, false, false, true, false, true]
+    );
+}
+
+#[test]
+fn gamepad_sticks_report_analog_keys() {
+    let (_lock, mut ctx) = context();
+    let mut platform = scripted_platform(&mut ctx, VitaPlatformBuilder::new());
+    let keys = [Key::GamepadLStickRight, Key::GamepadLStickLeft];
+    let mut right = |lx: u8| {
+        platform.input_mut().pad.lx = lx;
+        key_frame(&mut ctx, &mut platform, &keys)[0]
+    };
+
+    // Inside the dead zone.
+    assert_eq!(right(128 + 25), (false, 0.));
+    // Half deflected: 64/127 of travel, rescaled past the dead zone.
+    let (down, value) = right(128 + 64);
+    let expected = (64. / 127. - 0.25) / 0.75;
+    assert!(down);
+    assert!((value - expected).abs() < 1e-4, "{} != {}", value, expected);
+    assert_eq!(right(255), (true, 1.));
+
+    // Full left deflection, the opposite direction released.
+    platform.input_mut().pad.lx = 0;
+    assert_eq!(
+        key_frame(&mut ctx, &mut platform, &keys),
+        [(false, 0.), (true, 1.)]
+    );
+}
+
+/// A platform with the stick mouse on, moving `speed` pixels a second at full deflection.
+fn mousestick_platform(ctx: &mut imgui::Context, speed: f32) -> ScriptedPlatform {
+    scripted_platform(
+        ctx,
+        VitaPlatformBuilder::new()
+            .mousestick_usage(true)
+            .mousestick_speed(speed),
+    )
+}
+
+#[test]
+fn mousestick_moves_by_speed_and_delta_time() {
+    let (_lock, mut ctx) = context();
+    let mut platform = mousestick_platform(&mut ctx, 256.);
+    assert_eq!(frame(&mut ctx, &mut platform).pos, [0., 0.]);
+
+    // Full right deflection for 1/64, 1/64 and 2/64 seconds.
+    platform.input_mut().pad.lx = 255;
+    assert_eq!(frame(&mut ctx, &mut platform).pos, [4., 0.]);
+    assert_eq!(frame(&mut ctx, &mut platform).pos, [8., 0.]);
+    platform.clock_mut().advance(15_625);
+    assert_eq!(frame(&mut ctx, &mut platform).pos, [16., 0.]);
+
+    platform.input_mut().pad.lx = 128;
+    platform.input_mut().pad.ly = 255;
+    assert_eq!(frame(&mut ctx, &mut platform).pos, [16., 4.]);
+}
+
+#[test]
+fn mousestick_carries_sub_pixel_motion() {
+    let (_lock, mut ctx) = context();
+    let mut platform = mousestick_platform(&mut ctx, 32.);
+    frame(&mut ctx, &mut platform);
+
+    platform.input_mut().pad.lx = 255;
+    let xs: Vec<_> = (0..4)
+        .map(|_| frame(&mut ctx, &mut platform).pos[0])
+        .collect();
+    assert_eq!(xs, [0., 1., 1., 2.]);
+}
+
+#[test]
+fn mousestick_triggers_hold_mouse_buttons() {
+    let (_lock, mut ctx) = context();
+    let mut platform = mousestick_platform(&mut ctx, 256.);
+    let mut buttons = |buttons: u32| {
+        platform.input_mut().pad.buttons = buttons;
+        frame(&mut ctx, &mut platform).down
+    };
+
+    assert_eq!(buttons(SCE_CTRL_LTRIGGER), [true, false]);
+    assert_eq!(buttons(SCE_CTRL_LTRIGGER | SCE_CTRL_RTRIGGER), [true, true]);
+    assert_eq!(buttons(SCE_CTRL_RTRIGGER), [false, true]);
+    assert_eq!(buttons(0), [false, false]);
+}
+
+/// Runs a frame and returns whether the left button, L1 and the left stick's right direction
+/// were down, and whether ImGui drew the cursor.
+fn stick_frame(ctx: &mut imgui::Context, platform: &mut ScriptedPlatform) -> [bool; 4] {
+    platform.prepare_frame(ctx.io_mut());
+    let ui = ctx.new_frame();
+    let state = [
+        ui.io().mouse_down[0],
+        ui.is_key_down(Key::GamepadL1),
+        ui.is_key_down(Key::GamepadLStickRight),
+        ui.io().mouse_draw_cursor,
+    ];
+    ctx.render();
+    state
+}
+
+#[test]
+fn mousestick_takes_over_the_stick_and_triggers() {
+    let (_lock, mut ctx) = context();
+    let mut platform = mousestick_platform(&mut ctx, 256.);
+    let pad = &mut platform.input_mut().pad;
+    pad.lx = 255;
+    pad.buttons = SCE_CTRL_LTRIGGER;
+
+    assert_eq!(
+        stick_frame(&mut ctx, &mut platform),
+        [true, false, false, true]
+    );
+
+    // Turning it off releases the held button and hands the stick back to navigation.
+    platform.set_mousestick_usage(false);
+    assert!(!platform.mousestick_usage());
+    assert_eq!(
+        stick_frame(&mut ctx, &mut platform),
+        [false, true, true, false]
+    );
+}
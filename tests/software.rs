@@ -0,0 +1,444 @@
+mod common;
+
+use imgui::DrawCmd;
+use imgui_rs_vitagl_renderer::backend::GlBackend;
+use imgui_rs_vitagl_renderer::gl::*;
+use imgui_rs_vitagl_renderer::software::{GlCommand, SoftwareGl};
+use imgui_rs_vitagl_renderer::{
+    DrawMode, FrameAdvance, ImguiRenderer, ImguiRendererBuilder, Pipeline, RendererError,
+    ShaderSource, TextureData, TextureFilter,
+};
+
+/// A 2x2 texture: red, green / blue, white.
+const CHECKER: [u8; 16] = [
+    255, 0, 0, 255, 0, 255, 0, 255, //
+    0, 0, 255, 255, 255, 255, 255, 255,
+];
+
+/// Renders an image of `CHECKER`, a half transparent quad over it and a clipped white quad, and
+/// checks the result against the `textured_blended_scissored` golden. Returns the checker's GL
+/// texture name.
+fn render_golden_frame(
+    ctx: &mut imgui::Context,
+    renderer: &mut ImguiRenderer<SoftwareGl>,
+) -> GLuint {
+    let texture = renderer
+        .insert_texture(2, 2, TextureData::Rgba(&CHECKER))
+        .unwrap();
+    let texture_name = renderer.textures().get(texture).unwrap().name;
+
+    let ui = ctx.new_frame();
+    let draw_list = ui.get_background_draw_list();
+    draw_list.add_image(texture, [0., 0.], [32., 32.]).build();
+    draw_list
+        .add_rect([16., 16.], [48., 48.], [0., 0., 1., 0.5])
+        .filled(true)
+        .build();
+    draw_list.with_clip_rect([40., 0.], [64., 24.], || {
+        draw_list
+            .add_rect([32., 0.], [64., 32.], [1., 1., 1., 1.])
+            .filled(true)
+            .build();
+    });
+    drop(draw_list);
+
+    renderer.gl_mut().clear([0, 0, 0, 255]);
+    renderer.gl_mut().take_commands();
+    renderer.render(ctx.render()).unwrap();
+
+    let gl = renderer.gl();
+    let rgb = |x, y| {
+        let [r, g, b, _] = gl.pixel(x, y);
+        [r, g, b]
+    };
+    // Texels, nearest sampled.
+    assert_eq!(rgb(4, 4), [255, 0, 0]);
+    assert_eq!(rgb(20, 4), [0, 255, 0]);
+    assert_eq!(rgb(4, 20), [0, 0, 255]);
+    // Half transparent blue over the white texel and over the clear color.
+    for (actual, expected) in [(rgb(20, 20), [127, 127, 255]), (rgb(40, 40), [0, 0, 128])] {
+        assert!(
+            actual.iter().zip(expected).all(|(a, e)| a.abs_diff(e) <= 1),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+    // The white rectangle only shows inside its clip rect.
+    assert_eq!(rgb(50, 4), [255, 255, 255]);
+    assert_eq!(rgb(36, 4), [0, 0, 0]);
+    assert_eq!(rgb(50, 28), [0, 0, 0]);
+
+    common::assert_golden("textured_blended_scissored", gl);
+    texture_name
+}
+
+#[test]
+fn textured_blended_scissored_frame() {
+    let (_lock, mut ctx, mut renderer) = common::software_renderer(
+        ImguiRendererBuilder::new().texture_filter(TextureFilter::Nearest),
+    );
+    let texture_name = render_golden_frame(&mut ctx, &mut renderer);
+
+    let gl = renderer.gl();
+    let commands = gl.commands();
+    assert!(commands.contains(&GlCommand::Enable(GL_BLEND)));
+    assert!(commands.contains(&GlCommand::Enable(GL_SCISSOR_TEST)));
+    assert!(commands.contains(&GlCommand::BlendFunc {
+        sfactor: GL_SRC_ALPHA,
+        dfactor: GL_ONE_MINUS_SRC_ALPHA,
+    }));
+    let draws: Vec<_> = commands
+        .iter()
+        .filter_map(|command| match command {
+            GlCommand::DrawObjects {
+                texture, scissor, ..
+            } => Some((*texture, *scissor)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(draws.len(), 3);
+    assert_eq!(draws[0], (texture_name, Some([0, 0, 64, 64])));
+    // GL scissor boxes start at the bottom left.
+    assert_eq!(draws[2].1, Some([40, 40, 24, 24]));
+}
+
+#[test]
+fn fixed_function_indexed_matches_golden_frame() {
+    let (_lock, mut ctx, mut renderer) = common::software_renderer(
+        ImguiRendererBuilder::new()
+            .texture_filter(TextureFilter::Nearest)
+            .pipeline(Pipeline::FixedFunction)
+            .draw_mode(DrawMode::Indexed),
+    );
+    let texture_name = render_golden_frame(&mut ctx, &mut renderer);
+
+    let commands = renderer.gl().commands();
+    let stride = std::mem::size_of::<imgui::DrawVert>() as GLsizei;
+    assert!(commands.contains(&GlCommand::VertexPointer {
+        size: 2,
+        ty: GL_FLOAT,
+        stride,
+    }));
+    assert!(commands.contains(&GlCommand::TexCoordPointer {
+        size: 2,
+        ty: GL_FLOAT,
+        stride,
+    }));
+    assert!(commands.contains(&GlCommand::ColorPointer {
+        size: 4,
+        ty: GL_UNSIGNED_BYTE,
+        stride,
+    }));
+    assert!(!commands
+        .iter()
+        .any(|command| matches!(command, GlCommand::DrawObjects { .. })));
+    let draws: Vec<_> = commands
+        .iter()
+        .filter_map(|command| match command {
+            GlCommand::DrawElements {
+                texture, scissor, ..
+            } => Some((*texture, *scissor)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(draws.len(), 3);
+    assert_eq!(draws[0], (texture_name, Some([0, 0, 64, 64])));
+    assert_eq!(draws[2].1, Some([40, 40, 24, 24]));
+
+    drop(renderer);
+    drop(ctx);
+}
+
+#[test]
+fn shader_pipeline_matches_golden_frame() {
+    for draw_mode in [DrawMode::Deindexed, DrawMode::Indexed] {
+        let (_lock, mut ctx, mut renderer) = common::software_renderer(
+            ImguiRendererBuilder::new()
+                .texture_filter(TextureFilter::Nearest)
+                .pipeline(Pipeline::Shaders(ShaderSource::default()))
+                .draw_mode(draw_mode),
+        );
+        render_golden_frame(&mut ctx, &mut renderer);
+
+        let commands = renderer.gl().commands();
+        let programs: Vec<_> = commands
+            .iter()
+            .filter_map(|command| match command {
+                GlCommand::UseProgram(program) => Some(*program),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(programs.len(), 2, "{:?}", draw_mode);
+        assert_ne!(programs[0], 0);
+        assert_eq!(programs[1], 0);
+        // Position, texcoord and color.
+        for index in 0..3 {
+            assert!(commands.contains(&GlCommand::EnableVertexAttribArray(index)));
+            assert!(commands.contains(&GlCommand::DisableVertexAttribArray(index)));
+        }
+        assert!(!commands
+            .iter()
+            .any(|command| matches!(command, GlCommand::EnableClientState(_))));
+        match draw_mode {
+            DrawMode::Deindexed => {
+                let mapped: Vec<_> = commands
+                    .iter()
+                    .filter_map(|command| match command {
+                        GlCommand::VertexAttribPointerMapped { index } => Some(*index),
+                        _ => None,
+                    })
+                    .collect();
+                assert_eq!(mapped, [0, 1, 2].repeat(3));
+            }
+            DrawMode::Indexed => {
+                let stride = std::mem::size_of::<imgui::DrawVert>() as GLsizei;
+                assert!(commands.contains(&GlCommand::VertexAttribPointer {
+                    index: 0,
+                    size: 2,
+                    ty: GL_FLOAT,
+                    normalized: false,
+                    stride,
+                }));
+                assert!(commands.contains(&GlCommand::VertexAttribPointer {
+                    index: 2,
+                    size: 4,
+                    ty: GL_UNSIGNED_BYTE,
+                    normalized: true,
+                    stride,
+                }));
+            }
+        }
+
+        drop(renderer);
+        drop(ctx);
+    }
+}
+
+#[test]
+fn render_restores_gl_state() {
+    for pipeline in [
+        Pipeline::FixedFunction,
+        Pipeline::Shaders(ShaderSource::default()),
+    ] {
+        assert_restores_gl_state(ImguiRendererBuilder::new().pipeline(pipeline));
+    }
+}
+
+fn assert_restores_gl_state(builder: ImguiRendererBuilder) {
+    let (_lock, mut ctx, mut renderer) = common::software_renderer(builder);
+
+    let gl = renderer.gl_mut();
+    gl.enable(GL_CULL_FACE);
+    gl.enable(GL_DEPTH_TEST);
+    gl.blend_func(GL_ONE, GL_ZERO);
+
+    let ui = ctx.new_frame();
+    ui.get_background_draw_list()
+        .add_rect([0., 0.], [8., 8.], [1., 1., 1., 1.])
+        .filled(true)
+        .build();
+    renderer.render(ctx.render()).unwrap();
+
+    let gl = renderer.gl_mut();
+    assert!(gl.is_enabled(GL_CULL_FACE));
+    assert!(gl.is_enabled(GL_DEPTH_TEST));
+    for cap in [GL_BLEND, GL_SCISSOR_TEST, GL_TEXTURE_2D] {
+        assert!(!gl.is_enabled(cap), "{:#x} left enabled", cap);
+    }
+    let mut blend = [0; 2];
+    gl.get_integerv(GL_BLEND_SRC, &mut blend[..1]);
+    gl.get_integerv(GL_BLEND_DST, &mut blend[1..]);
+    assert_eq!(blend, [GL_ONE as i32, GL_ZERO as i32]);
+    let mut program = [-1];
+    gl.get_integerv(GL_CURRENT_PROGRAM, &mut program);
+    assert_eq!(program, [0]);
+}
+
+#[test]
+fn empty_clip_rects_are_skipped() {
+    let (_lock, mut ctx, mut renderer) = common::software_renderer(ImguiRendererBuilder::new());
+
+    let ui = ctx.new_frame();
+    let draw_list = ui.get_background_draw_list();
+    for (min, max) in [([40., 40.], [20., 20.]), ([70., 0.], [90., 64.])] {
+        draw_list.with_clip_rect(min, max, || {
+            draw_list
+                .add_rect([0., 0.], [64., 64.], [1., 1., 1., 1.])
+                .filled(true)
+                .build();
+        });
+    }
+    drop(draw_list);
+    renderer.gl_mut().take_commands();
+    renderer.render(ctx.render()).unwrap();
+
+    let gl = renderer.gl_mut();
+    assert!(!gl
+        .commands()
+        .iter()
+        .any(|command| matches!(command, GlCommand::DrawObjects { .. })));
+    assert_eq!(gl.get_error(), GL_NO_ERROR);
+}
+
+#[test]
+fn callbacks_reset_indexed_vertex_pointers() {
+    let (_lock, mut ctx, mut renderer) =
+        common::software_renderer(ImguiRendererBuilder::new().draw_mode(DrawMode::Indexed));
+
+    let ui = ctx.new_frame();
+    let draw_list = ui.get_background_draw_list();
+    draw_list
+        .add_rect([0., 0.], [8., 8.], [1., 1., 1., 1.])
+        .filled(true)
+        .build();
+    draw_list.add_callback(|| {}).build();
+    draw_list
+        .add_rect([8., 8.], [16., 16.], [1., 1., 1., 1.])
+        .filled(true)
+        .build();
+    drop(draw_list);
+    renderer.gl_mut().take_commands();
+    renderer.render(ctx.render()).unwrap();
+
+    let vertex_pointers = renderer
+        .gl()
+        .commands()
+        .iter()
+        .filter(|command| matches!(command, GlCommand::VertexPointer { .. }))
+        .count();
+    assert_eq!(vertex_pointers, 2);
+}
+
+#[test]
+fn manual_frames_share_a_vertex_region() {
+    // Three regions of 6 vertices, one filled rect each.
+    let builder = ImguiRendererBuilder::new()
+        .index_buffer_len(6)
+        .mempool_size(18);
+    for frame_advance in [FrameAdvance::Render, FrameAdvance::Manual] {
+        let (_lock, mut ctx, mut renderer) =
+            common::software_renderer(builder.clone().frame_advance(frame_advance));
+        let render = |ctx: &mut imgui::Context, renderer: &mut ImguiRenderer<SoftwareGl>| {
+            ctx.new_frame()
+                .get_background_draw_list()
+                .add_rect([0., 0.], [8., 8.], [1., 1., 1., 1.])
+                .filled(true)
+                .build();
+            renderer.render(ctx.render())
+        };
+
+        assert_eq!(render(&mut ctx, &mut renderer), Ok(()));
+        let second = render(&mut ctx, &mut renderer);
+        match frame_advance {
+            FrameAdvance::Render => assert_eq!(second, Ok(())),
+            // The second pass of a swap appends to the first one's region.
+            FrameAdvance::Manual => assert_eq!(
+                second,
+                Err(RendererError::BufferOverflow {
+                    needed: 12,
+                    capacity: 6,
+                })
+            ),
+        }
+        renderer.end_frame();
+        assert_eq!(render(&mut ctx, &mut renderer), Ok(()));
+
+        drop(renderer);
+        drop(ctx);
+    }
+}
+
+/// Renders three rects of different colors in a single draw command and returns the vertex
+/// count of every `DrawObjects` and the frame.
+fn render_one_command(builder: ImguiRendererBuilder) -> (Vec<usize>, Vec<u8>) {
+    let (_lock, mut ctx, mut renderer) = common::software_renderer(builder);
+    let ui = ctx.new_frame();
+    let draw_list = ui.get_background_draw_list();
+    for (i, color) in [[1., 0., 0., 1.], [0., 1., 0., 1.], [0., 0., 1., 0.5]]
+        .into_iter()
+        .enumerate()
+    {
+        let min = i as f32 * 12.;
+        draw_list
+            .add_rect([min, min], [min + 24., min + 24.], color)
+            .filled(true)
+            .build();
+    }
+    drop(draw_list);
+    renderer.gl_mut().clear([0, 0, 0, 255]);
+    renderer.gl_mut().take_commands();
+    renderer.render(ctx.render()).unwrap();
+
+    let gl = renderer.gl();
+    let draws = gl
+        .commands()
+        .iter()
+        .filter_map(|command| match command {
+            GlCommand::DrawObjects { vertices, .. } => Some(vertices.len()),
+            _ => None,
+        })
+        .collect();
+    let pixels = gl.pixels().to_vec();
+    drop(renderer);
+    drop(ctx);
+    (draws, pixels)
+}
+
+#[test]
+fn draws_are_split_to_fit_the_index_buffer() {
+    let (whole, expected) = render_one_command(ImguiRendererBuilder::new());
+    assert_eq!(whole, [18]);
+
+    let (split, pixels) = render_one_command(ImguiRendererBuilder::new().index_buffer_len(6));
+    assert_eq!(split, [6, 6, 6]);
+    assert!(pixels == expected, "split draws changed the frame");
+    // Chunks hold whole triangles.
+    let (split, pixels) = render_one_command(ImguiRendererBuilder::new().index_buffer_len(8));
+    assert_eq!(split, [6, 6, 6]);
+    assert!(pixels == expected, "split draws changed the frame");
+}
+
+#[test]
+fn draw_lists_past_64k_vertices_use_vtx_offset() {
+    for draw_mode in [DrawMode::Deindexed, DrawMode::Indexed] {
+        let (_lock, mut ctx, mut renderer) =
+            common::software_renderer(ImguiRendererBuilder::new().draw_mode(draw_mode));
+
+        // 17000 rects of 4 vertices, the last one red.
+        let ui = ctx.new_frame();
+        let draw_list = ui.get_background_draw_list();
+        for _ in 0..16_999 {
+            draw_list
+                .add_rect([0., 0.], [1., 1.], [1., 1., 1., 1.])
+                .filled(true)
+                .build();
+        }
+        draw_list
+            .add_rect([40., 40.], [48., 48.], [1., 0., 0., 1.])
+            .filled(true)
+            .build();
+        drop(draw_list);
+        let draw_data = ctx.render();
+        let vtx_offsets = draw_data
+            .draw_lists()
+            .flat_map(|draw_list| draw_list.commands())
+            .filter(|command| {
+                matches!(command, DrawCmd::Elements { cmd_params, .. } if cmd_params.vtx_offset > 0)
+            })
+            .count();
+        assert!(vtx_offsets > 0, "{:?}", draw_mode);
+
+        renderer.gl_mut().clear([0, 0, 0, 255]);
+        assert_eq!(renderer.render(draw_data), Ok(()), "{:?}", draw_mode);
+        assert_eq!(
+            renderer.gl().pixel(44, 44),
+            [255, 0, 0, 255],
+            "{:?}",
+            draw_mode
+        );
+
+        drop(renderer);
+        drop(ctx);
+    }
+}
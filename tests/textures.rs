@@ -0,0 +1,286 @@
+mod common;
+
+use imgui::{FontConfig, FontSource, TextureId};
+use imgui_rs_vitagl_renderer::backend::GlBackend;
+use imgui_rs_vitagl_renderer::gl::*;
+use imgui_rs_vitagl_renderer::software::{GlCommand, SoftwareGl};
+use imgui_rs_vitagl_renderer::{
+    FontAtlasFormat, ImguiRendererBuilder, RendererError, TextureData, TextureFilter,
+};
+
+/// Leaves a GL_INVALID_ENUM pending on `gl`.
+fn raise_stale_error(gl: &mut SoftwareGl) {
+    gl.tex_image_2d(GL_TEXTURE_2D, 0, 0, 1, 1, 0, GL_UNSIGNED_BYTE, &[0]);
+}
+
+#[test]
+fn stale_gl_errors_do_not_fail_uploads() {
+    let _lock = common::lock_imgui();
+    let mut ctx = imgui::Context::create();
+    ctx.set_ini_filename(None);
+    let mut gl = SoftwareGl::new(64, 64);
+    raise_stale_error(&mut gl);
+    let mut renderer = ImguiRendererBuilder::new()
+        .build_with_backend(&mut ctx, gl)
+        .unwrap();
+
+    raise_stale_error(renderer.gl_mut());
+    renderer
+        .insert_texture(1, 1, TextureData::Rgba(&[255; 4]))
+        .unwrap();
+    raise_stale_error(renderer.gl_mut());
+    renderer
+        .set_texture_filter(TextureFilter::Nearest, false)
+        .unwrap();
+    assert_eq!(renderer.gl_mut().get_error(), GL_NO_ERROR);
+}
+
+#[test]
+fn update_texture_replaces_pixels_and_size() {
+    let (_lock, _ctx, mut renderer) = common::software_renderer(ImguiRendererBuilder::new());
+    let texture = renderer
+        .insert_texture(1, 1, TextureData::Rgba(&[255; 4]))
+        .unwrap();
+    let name = renderer.textures().get(texture).unwrap().name;
+    renderer.gl_mut().take_commands();
+
+    renderer
+        .update_texture(texture, 2, 1, TextureData::Alpha(&[0, 255]))
+        .unwrap();
+    let updated = renderer.textures().get(texture).unwrap();
+    assert_eq!((updated.width, updated.height), (2, 1));
+    assert!(renderer.gl().commands().contains(&GlCommand::TexImage2D {
+        texture: name,
+        level: 0,
+        internal_format: GL_ALPHA as GLint,
+        width: 2,
+        height: 1,
+        format: GL_ALPHA,
+    }));
+
+    assert!(matches!(
+        renderer.update_texture(texture, 2, 2, TextureData::Alpha(&[0, 255])),
+        Err(RendererError::TextureData {
+            expected: 4,
+            actual: 2
+        })
+    ));
+    let unchanged = renderer.textures().get(texture).unwrap();
+    assert_eq!((unchanged.width, unchanged.height), (2, 1));
+}
+
+#[test]
+fn rgba_data_must_match_the_size_exactly() {
+    let (_lock, _ctx, mut renderer) = common::software_renderer(ImguiRendererBuilder::new());
+    let textures = renderer.textures().len();
+    for len in [3, 5, 7] {
+        assert_eq!(
+            renderer.insert_texture(1, 1, TextureData::Rgba(&[255; 8][..len])),
+            Err(RendererError::TextureData {
+                expected: 4,
+                actual: len
+            })
+        );
+    }
+    // A size whose byte count overflows `usize` never matches.
+    assert_eq!(
+        renderer.insert_texture(u32::MAX, u32::MAX, TextureData::Rgba(&[])),
+        Err(RendererError::TextureData {
+            expected: usize::MAX,
+            actual: 0
+        })
+    );
+    assert_eq!(renderer.textures().len(), textures);
+}
+
+#[test]
+fn remove_texture_deletes_it() {
+    let (_lock, _ctx, mut renderer) = common::software_renderer(ImguiRendererBuilder::new());
+    let texture = renderer
+        .insert_texture(1, 1, TextureData::Rgba(&[255; 4]))
+        .unwrap();
+    let name = renderer.textures().get(texture).unwrap().name;
+
+    renderer.remove_texture(texture).unwrap();
+    assert!(renderer.textures().get(texture).is_none());
+    assert!(renderer
+        .gl()
+        .commands()
+        .contains(&GlCommand::DeleteTexture(name)));
+    assert!(matches!(
+        renderer.remove_texture(texture),
+        Err(RendererError::UnknownTexture(id)) if id == texture
+    ));
+    assert!(matches!(
+        renderer.update_texture(texture, 1, 1, TextureData::Rgba(&[255; 4])),
+        Err(RendererError::UnknownTexture(id)) if id == texture
+    ));
+}
+
+#[test]
+fn removed_font_texture_is_uploaded_again() {
+    let (_lock, mut ctx, mut renderer) = common::software_renderer(ImguiRendererBuilder::new());
+    renderer.remove_texture(ctx.fonts().tex_id).unwrap();
+    renderer.gl_mut().take_commands();
+
+    renderer.prepare_frame(ctx.fonts()).unwrap();
+    assert!(renderer
+        .gl()
+        .commands()
+        .iter()
+        .any(|command| matches!(command, GlCommand::TexImage2D { .. })));
+    assert!(renderer.textures().get(ctx.fonts().tex_id).is_some());
+
+    let ui = ctx.new_frame();
+    ui.get_background_draw_list()
+        .add_text([0., 0.], [1., 1., 1., 1.], "font");
+    renderer.render(ctx.render()).unwrap();
+}
+
+#[test]
+fn paletted_textures_expand_through_the_palette() {
+    let (_lock, mut ctx, mut renderer) = common::software_renderer(
+        ImguiRendererBuilder::new().texture_filter(TextureFilter::Nearest),
+    );
+    // The second index is past the end of the palette and comes out transparent.
+    let texture = renderer
+        .insert_texture(
+            2,
+            1,
+            TextureData::Paletted {
+                indices: &[1, 7],
+                palette: &[[0, 0, 0, 255], [255, 0, 0, 255]],
+            },
+        )
+        .unwrap();
+
+    let ui = ctx.new_frame();
+    ui.get_background_draw_list()
+        .add_image(texture, [0., 0.], [32., 32.])
+        .build();
+    renderer.gl_mut().clear([0, 0, 255, 255]);
+    renderer.render(ctx.render()).unwrap();
+
+    assert_eq!(renderer.gl().pixel(8, 8), [255, 0, 0, 255]);
+    assert_eq!(renderer.gl().pixel(24, 8), [0, 0, 255, 255]);
+}
+
+/// The `(width, height)` of every `TexImage2D` in `gl`'s command log.
+fn uploads(gl: &SoftwareGl) -> Vec<(GLsizei, GLsizei)> {
+    gl.commands()
+        .iter()
+        .filter_map(|command| match command {
+            GlCommand::TexImage2D { width, height, .. } => Some((*width, *height)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn prepare_frame_uploads_added_fonts() {
+    let (_lock, mut ctx, mut renderer) = common::software_renderer(ImguiRendererBuilder::new());
+    renderer.prepare_frame(ctx.fonts()).unwrap();
+    let first = ctx.fonts().build_alpha8_texture().height;
+    renderer.gl_mut().take_commands();
+
+    ctx.fonts().add_font(&[FontSource::DefaultFontData {
+        config: Some(FontConfig {
+            size_pixels: 40.,
+            ..FontConfig::default()
+        }),
+    }]);
+    renderer.prepare_frame(ctx.fonts()).unwrap();
+
+    let atlas = ctx.fonts().build_alpha8_texture();
+    let size = (atlas.width as GLsizei, atlas.height as GLsizei);
+    assert_ne!(atlas.height, first);
+    assert_eq!(uploads(renderer.gl()), [size]);
+}
+
+#[test]
+fn prepare_frame_uploads_after_tex_id_reset() {
+    let (_lock, mut ctx, mut renderer) = common::software_renderer(ImguiRendererBuilder::new());
+    let font_texture = ctx.fonts().tex_id;
+    renderer.gl_mut().take_commands();
+    renderer.prepare_frame(ctx.fonts()).unwrap();
+    assert!(uploads(renderer.gl()).is_empty());
+
+    ctx.fonts().tex_id = TextureId::new(0);
+    renderer.prepare_frame(ctx.fonts()).unwrap();
+    assert_eq!(uploads(renderer.gl()).len(), 1);
+    assert_eq!(ctx.fonts().tex_id, font_texture);
+}
+
+#[test]
+fn rgba32_font_atlas_uploads_rgba() {
+    let (_lock, mut ctx, renderer) = common::software_renderer(
+        ImguiRendererBuilder::new().font_atlas_format(FontAtlasFormat::Rgba32),
+    );
+    let name = renderer.textures().get(ctx.fonts().tex_id).unwrap().name;
+    let atlas = ctx.fonts().build_rgba32_texture();
+    assert!(renderer.gl().commands().contains(&GlCommand::TexImage2D {
+        texture: name,
+        level: 0,
+        internal_format: GL_RGBA as GLint,
+        width: atlas.width as GLsizei,
+        height: atlas.height as GLsizei,
+        format: GL_RGBA,
+    }));
+}
+
+/// The commands `gl` logged while `texture` was bound.
+fn commands_on(gl: &SoftwareGl, texture: GLuint) -> Vec<&GlCommand> {
+    let mut bound = 0;
+    gl.commands()
+        .iter()
+        .filter(|command| {
+            if let GlCommand::BindTexture { texture: name, .. } = command {
+                bound = *name;
+            }
+            bound == texture
+        })
+        .collect()
+}
+
+#[test]
+fn mipmaps_reach_the_font_texture() {
+    let (_lock, mut ctx, renderer) = common::software_renderer(
+        ImguiRendererBuilder::new()
+            .texture_filter(TextureFilter::Linear)
+            .mipmaps(true),
+    );
+    let name = renderer.textures().get(ctx.fonts().tex_id).unwrap().name;
+
+    let commands = commands_on(renderer.gl(), name);
+    assert!(commands.contains(&&GlCommand::TexParameteri {
+        target: GL_TEXTURE_2D,
+        pname: GL_TEXTURE_MIN_FILTER,
+        param: GL_LINEAR_MIPMAP_LINEAR as GLint,
+    }));
+    assert!(commands.contains(&&GlCommand::GenerateMipmap(GL_TEXTURE_2D)));
+}
+
+#[test]
+fn texture_filter_changes_reach_user_textures() {
+    let (_lock, _ctx, mut renderer) = common::software_renderer(ImguiRendererBuilder::new());
+    let texture = renderer
+        .insert_texture(1, 1, TextureData::Rgba(&[255; 4]))
+        .unwrap();
+    let name = renderer.textures().get(texture).unwrap().name;
+    renderer.gl_mut().take_commands();
+
+    renderer
+        .set_texture_filter(TextureFilter::Nearest, false)
+        .unwrap();
+    let commands = commands_on(renderer.gl(), name);
+    for pname in [GL_TEXTURE_MIN_FILTER, GL_TEXTURE_MAG_FILTER] {
+        assert!(commands.contains(&&GlCommand::TexParameteri {
+            target: GL_TEXTURE_2D,
+            pname,
+            param: GL_NEAREST as GLint,
+        }));
+    }
+    assert!(!commands
+        .iter()
+        .any(|command| matches!(command, GlCommand::GenerateMipmap(_))));
+}